@@ -0,0 +1,43 @@
+//! timing loop shared by the benchmarks
+
+use nom::IResult;
+use nom::types::CompleteStr;
+use std::time::{Duration, Instant};
+
+const ROUNDS: u32 = 20;
+
+/// applies `parser` on `doc` until it fails, and prints the best time
+/// over `ROUNDS` runs
+///
+/// the parser must match `records` times and consume the whole document,
+/// which is checked so the results are not optimized away
+pub fn run<'a, O, F>(name: &str, doc: &'a str, records: usize, parser: F)
+where
+  F: Fn(CompleteStr<'a>) -> IResult<CompleteStr<'a>, O>,
+{
+  let mut best: Option<Duration> = None;
+  for _ in 0..ROUNDS {
+    let start = Instant::now();
+    let mut input = CompleteStr(doc);
+    let mut count = 0;
+    while let Ok((i, _)) = parser(input) {
+      input = i;
+      count += 1;
+    }
+    assert_eq!(count, records);
+    assert_eq!(input.len(), 0);
+    let elapsed = start.elapsed();
+    best = match best {
+      Some(b) if b < elapsed => Some(b),
+      _ => Some(elapsed),
+    };
+  }
+
+  println!(
+    "{:>14}: {:?} per document ({} bytes), best of {}",
+    name,
+    best.unwrap(),
+    doc.len(),
+    ROUNDS
+  );
+}
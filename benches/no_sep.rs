@@ -0,0 +1,28 @@
+//! compares `sp` to `no_sep` on input without whitespace
+//!
+//! run with `cargo bench --bench no_sep`
+#[macro_use]
+extern crate nom;
+#[macro_use]
+extern crate nom_whitespace;
+
+mod common;
+
+use common::run;
+use nom::types::CompleteStr;
+use nom_whitespace::sp;
+
+type Fields<'a> = (CompleteStr<'a>, CompleteStr<'a>, CompleteStr<'a>);
+
+named!(record_sp<CompleteStr, Fields>, sep!(sp, tuple!(tag!("key"), tag!("="), tag!("value;"))));
+named!(record_no_sep<CompleteStr, Fields>, sep!(sp, without_ws!(tuple!(tag!("key"), tag!("="), tag!("value;")))));
+
+fn document() -> String {
+  "key=value;".repeat(100_000)
+}
+
+fn main() {
+  let doc = document();
+  run("sp", &doc, 100_000, record_sp);
+  run("without_ws", &doc, 100_000, record_no_sep);
+}
@@ -0,0 +1,44 @@
+//! compares `sp`, which is `#[inline(always)]`, to the same separator
+//! behind an `#[inline(never)]` call, and to a separator built with
+//! `eat_separator!`
+//!
+//! run with `cargo bench --bench sp`
+#[macro_use]
+extern crate nom;
+#[macro_use]
+extern crate nom_whitespace;
+
+mod common;
+
+use common::run;
+use nom::IResult;
+use nom::types::CompleteStr;
+use nom_whitespace::sp;
+
+named!(eat_sp<CompleteStr, CompleteStr>, eat_separator!(" \t\r\n"));
+
+#[inline(never)]
+fn sp_call(input: CompleteStr) -> IResult<CompleteStr, CompleteStr> {
+  sp(input)
+}
+
+named!(token_sp<CompleteStr, CompleteStr>, sep!(sp, tag!("token")));
+named!(token_call<CompleteStr, CompleteStr>, sep!(sp_call, tag!("token")));
+named!(token_eat<CompleteStr, CompleteStr>, sep!(eat_sp, tag!("token")));
+
+fn document() -> String {
+  let separators = [" ", "\t", "\n  ", "    \t", "\r\n", "        "];
+  let mut doc = String::new();
+  for i in 0..100_000 {
+    doc.push_str(separators[i % separators.len()]);
+    doc.push_str("token");
+  }
+  doc
+}
+
+fn main() {
+  let doc = document();
+  run("sp", &doc, 100_000, token_sp);
+  run("sp not inlined", &doc, 100_000, token_call);
+  run("eat_separator", &doc, 100_000, token_eat);
+}
@@ -0,0 +1,51 @@
+//! compares `ws!` to the same parser delimited by hand with `sp`
+//!
+//! `ws!` applies the separator once between two tokens, while the
+//! `delimited!` version applies it on both sides of each token, the
+//! second call finding nothing to consume. Both parse the same document
+//! of `RECORDS` records with mixed whitespace, and the best time per
+//! document of each is printed
+//!
+//! run with `cargo bench --bench ws_overhead`
+#[macro_use]
+extern crate nom;
+#[macro_use]
+extern crate nom_whitespace;
+
+mod common;
+
+use common::run;
+use nom::types::CompleteStr;
+use nom_whitespace::sp;
+
+type Fields<'a> = (CompleteStr<'a>, CompleteStr<'a>, CompleteStr<'a>, CompleteStr<'a>);
+
+named!(record_ws<CompleteStr, Fields>, ws!(tuple!(tag!("key"), tag!("="), tag!("value"), tag!(";"))));
+named!(record_delimited<CompleteStr, Fields>,
+  tuple!(
+    delimited!(sp, tag!("key"), sp),
+    delimited!(sp, tag!("="), sp),
+    delimited!(sp, tag!("value"), sp),
+    delimited!(sp, tag!(";"), sp)
+  )
+);
+
+const RECORDS: usize = 5_000;
+
+fn document() -> String {
+  let separators = [" ", "\t", "\n  ", "    \t", "\r\n", "        "];
+  let mut doc = String::new();
+  for i in 0..RECORDS {
+    for (j, token) in ["key", "=", "value", ";"].iter().enumerate() {
+      doc.push_str(separators[(i + j) % separators.len()]);
+      doc.push_str(token);
+    }
+  }
+  doc
+}
+
+fn main() {
+  let doc = document();
+  run("ws", &doc, RECORDS, record_ws);
+  run("delimited", &doc, RECORDS, record_delimited);
+}
@@ -15,15 +15,21 @@
 //!
 //! ```
 //! # #[macro_use] extern crate nom;
+//! # #[macro_use] extern crate nom_whitespace;
 //! # fn main() {
 //! named!(tuple<&[u8], (&[u8], &[u8]) >,
 //!   ws!(tuple!( take!(3), tag!("de") ))
 //! );
 //!
+//! # // with `ws_no_trailing`, the space before "fg" is left in place
+//! # if !cfg!(feature = "ws_no_trailing") {
 //! assert_eq!(
 //!   tuple(&b" \t abc de fg"[..]),
 //!  Ok((&b"fg"[..], (&b"abc"[..], &b"de"[..])))
 //! );
+//! # } else {
+//! # assert_eq!(tuple(&b" \t abc de fg"[..]), Ok((&b" fg"[..], (&b"abc"[..], &b"de"[..]))));
+//! # }
 //! # }
 //! ```
 //!
@@ -38,25 +44,13 @@
 //!
 //! ```
 //! # #[macro_use] extern crate nom;
+//! # #[macro_use] extern crate nom_whitespace;
 //! named!(pub space, eat_separator!(&b" \t"[..]));
 //!
 //! #[macro_export]
 //! macro_rules! sp (
 //!   ($i:expr, $($args:tt)*) => (
-//!     {
-//!       use nom::Convert;
-//!       use nom::Err;
-//!
-//!       match sep!($i, space, $($args)*) {
-//!         Err(e) => Err(e),
-//!         Ok((i1,o))    => {
-//!           match space(i1) {
-//!             Err(e) => Err(Err::convert(e)),
-//!             Ok((i2,_))    => Ok((i2, o))
-//!           }
-//!         }
-//!       }
-//!     }
+//!     ws_with!($i, space, $($args)*)
 //!   )
 //! );
 //!
@@ -95,22 +89,26 @@
 //!
 #![cfg_attr(not(feature = "std"), no_std)]
 
-#[macro_use]
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+extern crate alloc;
+#[cfg_attr(test, macro_use)]
 extern crate nom;
 
 pub mod lib {
   #[cfg(not(feature = "std"))]
   pub mod std {
-    pub use core::{option, result};
+    #[cfg(feature = "alloc")]
+    pub use alloc::{string, vec};
+    pub use core::{cell, convert, fmt, ops, option, result};
   }
 
   #[cfg(feature = "std")]
   pub mod std {
-    pub use std::{option, result};
+    pub use std::{cell, collections, convert, fmt, ops, option, result, string, vec};
   }
 
   pub mod nom {
-    pub use nom::{Err,Convert,IResult,ErrorKind, AsChar, FindToken, InputTakeAtPosition};
+    pub use nom::{Err,Context,Convert,IResult,ErrorKind,Needed, need_more_err, AsChar, AtEof, Compare, CompareResult, FindToken, InputIter, InputLength, InputTake, InputTakeAtPosition, Offset, ParseTo, Slice};
   }
 }
 
@@ -22,6 +22,84 @@ macro_rules! nom_tuple (
   ($($args:tt)*) => (tuple!($($args)*));
 );
 
+#[doc(hidden)]
+#[macro_export]
+macro_rules! nom_complete (
+  ($($args:tt)*) => (complete!($($args)*));
+);
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! nom_many0 (
+  ($($args:tt)*) => (many0!($($args)*));
+);
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! nom_many1 (
+  ($($args:tt)*) => (many1!($($args)*));
+);
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! nom_many_m_n (
+  ($($args:tt)*) => (many_m_n!($($args)*));
+);
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! nom_take (
+  ($($args:tt)*) => (take!($($args)*));
+);
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! nom_escaped (
+  ($($args:tt)*) => (escaped!($($args)*));
+);
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! nom_take_while_m_n (
+  ($($args:tt)*) => (take_while_m_n!($($args)*));
+);
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! nom_tag (
+  ($($args:tt)*) => (tag!($($args)*));
+);
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! nom_tag_no_case (
+  ($($args:tt)*) => (tag_no_case!($($args)*));
+);
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! nom_opt (
+  ($($args:tt)*) => (opt!($($args)*));
+);
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! nom_map_res (
+  ($($args:tt)*) => (map_res!($($args)*));
+);
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! nom_fold_many0 (
+  ($($args:tt)*) => (fold_many0!($($args)*));
+);
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! nom_return_error (
+  ($($args:tt)*) => (return_error!($($args)*));
+);
+
 #[doc(hidden)]
 #[macro_export]
 macro_rules! nom_permutation_init (
@@ -92,22 +170,20 @@ macro_rules! acc (
 macro_rules! wrap_sep (
   ($i:expr, $separator:expr, $submac:ident!( $($args:tt)* )) => ({
     use $crate::lib::std::result::Result::*;
-    use $crate::lib::nom::{Err,Convert,IResult};
-
-    fn unify_types<I,O,P,E>(_: &IResult<I,O,E>, _: &IResult<I,P,E>) {}
+    use $crate::lib::nom::{Err,Convert};
 
     let sep_res = ($separator)($i);
     match sep_res {
       Ok((i1,_))    => {
         let res = $submac!(i1, $($args)*);
-        unify_types(&sep_res, &res);
+        $crate::unify_types(&sep_res, &res);
         res
       },
       Err(e) => Err(Err::convert(e)),
     }
   });
   ($i:expr, $separator:expr, $f:expr) => (
-    wrap_sep!($i, $separator, nom_nom_call!($f))
+    wrap_sep!($i, $separator, nom_call!($f))
   );
 );
 
@@ -122,13 +198,184 @@ macro_rules! pair_sep (
     )
   );
   ($i:expr, $separator:path, $submac:ident!( $($args:tt)* ), $g:expr) => (
-    pair_sep!($i, $separator, $submac!($($args)*), nom_call!($g));
+    pair_sep!($i, $separator, $submac!($($args)*), nom_call!($g))
+  );
+  ($i:expr, $separator:path, $f:expr, $submac:ident!( $($args:tt)* )) => (
+    pair_sep!($i, $separator, nom_call!($f), $submac!($($args)*))
+  );
+  ($i:expr, $separator:path, $f:expr, $g:expr) => (
+    pair_sep!($i, $separator, nom_call!($f), nom_call!($g))
+  );
+);
+
+#[doc(hidden)]
+#[macro_export(local_inner_macros)]
+macro_rules! pair_ws_count_sep (
+  ($i:expr, $separator:path, $submac:ident!( $($args:tt)* ), $submac2:ident!( $($args2:tt)* )) => (
+    {
+      use $crate::lib::std::result::Result::*;
+      use $crate::lib::nom::{Convert,Err,InputLength};
+
+      match sep!($i, $separator, $submac!($($args)*)) {
+        Err(e)      => Err(e),
+        Ok((i1,o1)) => {
+          match ($separator)(i1.clone()) {
+            Err(e)      => Err(Err::convert(e)),
+            Ok((i2, _)) => {
+              let count = i1.input_len() - i2.input_len();
+              match sep!(i2, $separator, $submac2!($($args2)*)) {
+                Err(e)      => Err(e),
+                Ok((i3,o2)) => Ok((i3, (o1, o2, count)))
+              }
+            }
+          }
+        }
+      }
+    }
+  );
+  ($i:expr, $separator:path, $submac:ident!( $($args:tt)* ), $g:expr) => (
+    pair_ws_count_sep!($i, $separator, $submac!($($args)*), nom_call!($g))
+  );
+  ($i:expr, $separator:path, $f:expr, $submac:ident!( $($args:tt)* )) => (
+    pair_ws_count_sep!($i, $separator, nom_call!($f), $submac!($($args)*))
+  );
+  ($i:expr, $separator:path, $f:expr, $g:expr) => (
+    pair_ws_count_sep!($i, $separator, nom_call!($f), nom_call!($g))
+  );
+);
+
+/// left associative: `a op b op c` is folded as `fold(fold(a, op, b), op, c)`
+#[doc(hidden)]
+#[macro_export(local_inner_macros)]
+macro_rules! binop_level_sep (
+  ($i:expr, $separator:path, $submac:ident!( $($args:tt)* ), $submac2:ident!( $($args2:tt)* ), $fold:expr) => (
+    {
+      use $crate::lib::std::result::Result::*;
+      use $crate::lib::std::option::Option::*;
+      use $crate::lib::nom::{Err,InputLength};
+
+      match sep!($i, $separator, $submac!($($args)*)) {
+        Err(e)         => Err(e),
+        Ok((i1,first)) => {
+          let mut acc   = first;
+          let mut input = i1;
+          let mut error = None;
+
+          loop {
+            let len = input.input_len();
+            match sep!(input.clone(), $separator, $submac2!($($args2)*)) {
+              Err(Err::Error(_)) => break,
+              Err(e)             => {
+                error = Some(e);
+                break;
+              },
+              Ok((i2,op))        => match sep!(i2, $separator, $submac!($($args)*)) {
+                Err(Err::Error(_)) => break,
+                Err(e)             => {
+                  error = Some(e);
+                  break;
+                },
+                Ok((i3,rhs))       => {
+                  // infinite loop check: the operator and the operand matched nothing
+                  if i3.input_len() == len {
+                    break;
+                  }
+                  acc   = $fold(acc, op, rhs);
+                  input = i3;
+                }
+              }
+            }
+          }
+
+          match error {
+            Some(e) => Err(e),
+            None    => Ok((input, acc)),
+          }
+        }
+      }
+    }
+  );
+  ($i:expr, $separator:path, $submac:ident!( $($args:tt)* ), $g:expr, $fold:expr) => (
+    binop_level_sep!($i, $separator, $submac!($($args)*), nom_call!($g), $fold)
+  );
+  ($i:expr, $separator:path, $f:expr, $submac:ident!( $($args:tt)* ), $fold:expr) => (
+    binop_level_sep!($i, $separator, nom_call!($f), $submac!($($args)*), $fold)
+  );
+  ($i:expr, $separator:path, $f:expr, $g:expr, $fold:expr) => (
+    binop_level_sep!($i, $separator, nom_call!($f), nom_call!($g), $fold)
+  );
+);
+
+/// the second parser is applied with the separator on the output of the first one
+#[doc(hidden)]
+#[macro_export(local_inner_macros)]
+macro_rules! flat_map_ws_sep (
+  ($i:expr, $separator:path, $submac:ident!( $($args:tt)* ), $submac2:ident!( $($args2:tt)* )) => (
+    {
+      use $crate::lib::std::result::Result::*;
+      use $crate::lib::nom::{Convert,Err};
+
+      match sep!($i, $separator, $submac!($($args)*)) {
+        Err(e)    => Err(e),
+        Ok((i,o)) => match sep!(o, $separator, $submac2!($($args2)*)) {
+          Err(e)      => Err(Err::convert(e)),
+          Ok((_, o2)) => Ok((i, o2))
+        }
+      }
+    }
+  );
+  ($i:expr, $separator:path, $submac:ident!( $($args:tt)* ), $g:expr) => (
+    flat_map_ws_sep!($i, $separator, $submac!($($args)*), nom_call!($g))
+  );
+  ($i:expr, $separator:path, $f:expr, $submac:ident!( $($args:tt)* )) => (
+    flat_map_ws_sep!($i, $separator, nom_call!($f), $submac!($($args)*))
+  );
+  ($i:expr, $separator:path, $f:expr, $g:expr) => (
+    flat_map_ws_sep!($i, $separator, nom_call!($f), nom_call!($g))
+  );
+);
+
+/// every call to the separator, in `p` and its children, goes through
+/// the tagging separator
+#[doc(hidden)]
+#[macro_export(local_inner_macros)]
+macro_rules! separator_errors_sep (
+  ($i:expr, $separator:path, $($rest:tt)*) => (
+    {
+      use $crate::lib::std::result::Result::*;
+      use $crate::lib::nom::{Context,Err,ErrorKind};
+
+      let separator_fn = |input| match ($separator)(Clone::clone(&input)) {
+        res @ Err(Err::Error(Context::Code(_, ErrorKind::Custom(_)))) => res,
+        Err(Err::Error(e)) => {
+          Err(Err::Error(nom_ws_error_node_position!(input, ErrorKind::Custom($crate::SEPARATOR_ERROR), e)))
+        },
+        res => res,
+      };
+
+      sep!($i, separator_fn, $($rest)*)
+    }
+  );
+);
+
+#[doc(hidden)]
+#[macro_export(local_inner_macros)]
+macro_rules! head_then_sep_sep (
+  ($i:expr, $separator:path, $submac:ident!( $($args:tt)* ), $submac2:ident!( $($args2:tt)* )) => (
+    nom_tuple!(
+      $i,
+      $submac!($($args)*),
+      sep!($separator, $submac2!($($args2)*))
+    )
+  );
+  ($i:expr, $separator:path, $submac:ident!( $($args:tt)* ), $g:expr) => (
+    head_then_sep_sep!($i, $separator, $submac!($($args)*), nom_call!($g))
   );
   ($i:expr, $separator:path, $f:expr, $submac:ident!( $($args:tt)* )) => (
-    pair_sep!($i, $separator, nom_call!($f), $submac!($($args)*));
+    head_then_sep_sep!($i, $separator, nom_call!($f), $submac!($($args)*))
   );
   ($i:expr, $separator:path, $f:expr, $g:expr) => (
-    pair_sep!($i, $separator, nom_call!($f), nom_call!($g));
+    head_then_sep_sep!($i, $separator, nom_call!($f), nom_call!($g))
   );
 );
 
@@ -146,7 +393,7 @@ macro_rules! delimited_sep (
     }
   });
   ($i:expr, $separator:path, $f:expr, $($rest:tt)+) => (
-    delimited_sep!($i, $separator, nom_call!($f), $($rest)*);
+    delimited_sep!($i, $separator, nom_call!($f), $($rest)*)
   );
 );
 
@@ -164,7 +411,7 @@ macro_rules! separated_pair_sep (
     }
   });
   ($i:expr, $separator:path, $f:expr, $($rest:tt)+) => (
-    separated_pair_sep!($i, $separator, nom_call!($f), $($rest)*);
+    separated_pair_sep!($i, $separator, nom_call!($f), $($rest)*)
   );
 );
 
@@ -182,13 +429,13 @@ macro_rules! preceded_sep (
     }
   });
   ($i:expr, $separator:path, $submac:ident!( $($args:tt)* ), $g:expr) => (
-    preceded_sep!($i, $separator, $submac!($($args)*), nom_call!($g));
+    preceded_sep!($i, $separator, $submac!($($args)*), nom_call!($g))
   );
   ($i:expr, $separator:path, $f:expr, $submac:ident!( $($args:tt)* )) => (
-    preceded_sep!($i, $separator, nom_call!($f), $submac!($($args)*));
+    preceded_sep!($i, $separator, nom_call!($f), $submac!($($args)*))
   );
   ($i:expr, $separator:path, $f:expr, $g:expr) => (
-    preceded_sep!($i, $separator, nom_call!($f), nom_call!($g));
+    preceded_sep!($i, $separator, nom_call!($f), nom_call!($g))
   );
 );
 
@@ -206,13 +453,55 @@ macro_rules! terminated_sep (
     }
   });
   ($i:expr, $separator:path, $submac:ident!( $($args:tt)* ), $g:expr) => (
-    terminated_sep!($i, $separator, $submac!($($args)*), nom_call!($g));
+    terminated_sep!($i, $separator, $submac!($($args)*), nom_call!($g))
+  );
+  ($i:expr, $separator:path, $f:expr, $submac:ident!( $($args:tt)* )) => (
+    terminated_sep!($i, $separator, nom_call!($f), $submac!($($args)*))
+  );
+  ($i:expr, $separator:path, $f:expr, $g:expr) => (
+    terminated_sep!($i, $separator, nom_call!($f), nom_call!($g))
+  );
+);
+
+#[doc(hidden)]
+#[macro_export(local_inner_macros)]
+macro_rules! terminated_opt_sep (
+  ($i:expr, $separator:path, $submac:ident!( $($args:tt)* ), $submac2:ident!( $($args2:tt)* )) => (
+    {
+      use $crate::lib::std::result::Result::*;
+      use $crate::lib::nom::{Err,InputLength};
+
+      match sep!($i, $separator, $submac!($($args)*)) {
+        Err(e)      => Err(e),
+        Ok((i1,o))  => {
+          match sep!(i1.clone(), $separator, $submac2!($($args2)*)) {
+            Ok((i2,_))           => Ok((i2, o)),
+            Err(Err::Failure(e)) => Err(Err::Failure(e)),
+            Err(e)               => {
+              // the terminator can only be missing at the end of input
+              let rest = match ($separator)(i1.clone()) {
+                Ok((i,_)) => i,
+                Err(_)    => i1,
+              };
+              if rest.input_len() == 0 {
+                Ok((rest, o))
+              } else {
+                Err(e)
+              }
+            }
+          }
+        }
+      }
+    }
+  );
+  ($i:expr, $separator:path, $submac:ident!( $($args:tt)* ), $g:expr) => (
+    terminated_opt_sep!($i, $separator, $submac!($($args)*), nom_call!($g))
   );
   ($i:expr, $separator:path, $f:expr, $submac:ident!( $($args:tt)* )) => (
-    terminated_sep!($i, $separator, nom_call!($f), $submac!($($args)*));
+    terminated_opt_sep!($i, $separator, nom_call!($f), $submac!($($args)*))
   );
   ($i:expr, $separator:path, $f:expr, $g:expr) => (
-    terminated_sep!($i, $separator, nom_call!($f), nom_call!($g));
+    terminated_opt_sep!($i, $separator, nom_call!($f), nom_call!($g))
   );
 );
 
@@ -220,8 +509,20 @@ macro_rules! terminated_sep (
 #[doc(hidden)]
 #[macro_export(local_inner_macros)]
 macro_rules! tuple_sep (
+  ($i:expr, $separator:path, $parsed:tt, $e:ident?, $($rest:tt)*) => (
+    tuple_sep!($i, $separator, $parsed, nom_call!($e)?, $($rest)*)
+  );
+  ($i:expr, $separator:path, $parsed:tt, $submac:ident!( $($args:tt)* )?, $($rest:tt)*) => (
+    tuple_sep!($i, $separator, $parsed, opt!($submac!($($args)*)), $($rest)*)
+  );
+  ($i:expr, $separator:path, $parsed:tt, $e:ident?) => (
+    tuple_sep!($i, $separator, $parsed, nom_call!($e)?)
+  );
+  ($i:expr, $separator:path, $parsed:tt, $submac:ident!( $($args:tt)* )?) => (
+    tuple_sep!($i, $separator, $parsed, opt!($submac!($($args)*)))
+  );
   ($i:expr, $separator:path, ($($parsed:tt),*), $e:path, $($rest:tt)*) => (
-    tuple_sep!($i, $separator, ($($parsed),*), nom_call!($e), $($rest)*);
+    tuple_sep!($i, $separator, ($($parsed),*), nom_call!($e), $($rest)*)
   );
   ($i:expr, $separator:path, (), $submac:ident!( $($args:tt)* ), $($rest:tt)*) => (
     {
@@ -248,7 +549,7 @@ macro_rules! tuple_sep (
     }
   );
   ($i:expr, $separator:path, ($($parsed:tt),*), $e:path) => (
-    tuple_sep!($i, $separator, ($($parsed),*), nom_call!($e));
+    tuple_sep!($i, $separator, ($($parsed),*), nom_call!($e))
   );
   ($i:expr, $separator:path, (), $submac:ident!( $($args:tt)* )) => (
     {
@@ -276,7 +577,7 @@ macro_rules! tuple_sep (
   );
   ($i:expr, $separator:path, ($($parsed:expr),*)) => (
     {
-      ::sts::result::Result::Ok(($i, ($($parsed),*)))
+      $crate::lib::std::result::Result::Ok(($i, ($($parsed),*)))
     }
   );
 );
@@ -289,14 +590,15 @@ macro_rules! do_parse_sep (
   );
 
   (__impl $i:expr, $separator:path, $e:ident >> $($rest:tt)*) => (
-    do_parse_sep!(__impl $i, $separator, nom_call!($e) >> $($rest)*);
+    do_parse_sep!(__impl $i, $separator, nom_call!($e) >> $($rest)*)
   );
   (__impl $i:expr, $separator:path, $submac:ident!( $($args:tt)* ) >> $($rest:tt)*) => (
     {
       use $crate::lib::std::result::Result::*;
+      use $crate::lib::nom::{Convert,Err};
 
       match sep!($i, $separator, $submac!($($args)*)) {
-        Err(e) => Err(e),
+        Err(e) => Err(Err::convert(e)),
         Ok((i,_))     => {
           do_parse_sep!(__impl i, $separator, $($rest)*)
         },
@@ -305,15 +607,16 @@ macro_rules! do_parse_sep (
   );
 
   (__impl $i:expr, $separator:path, $field:ident : $e:ident >> $($rest:tt)*) => (
-    do_parse_sep!(__impl $i, $separator, $field: nom_call!($e) >> $($rest)*);
+    do_parse_sep!(__impl $i, $separator, $field: nom_call!($e) >> $($rest)*)
   );
 
   (__impl $i:expr, $separator:path, $field:ident : $submac:ident!( $($args:tt)* ) >> $($rest:tt)*) => (
     {
       use $crate::lib::std::result::Result::*;
+      use $crate::lib::nom::{Convert,Err};
 
       match sep!($i, $separator, $submac!($($args)*)) {
-        Err(e) => Err(e),
+        Err(e) => Err(Err::convert(e)),
         Ok((i,o))     => {
           let $field = o;
           do_parse_sep!(__impl i, $separator, $($rest)*)
@@ -324,14 +627,15 @@ macro_rules! do_parse_sep (
 
   // ending the chain
   (__impl $i:expr, $separator:path, $e:ident >> ( $($rest:tt)* )) => (
-    do_parse_sep!(__impl $i, $separator, nom_call!($e) >> ( $($rest)* ));
+    do_parse_sep!(__impl $i, $separator, nom_call!($e) >> ( $($rest)* ))
   );
 
   (__impl $i:expr, $separator:path, $submac:ident!( $($args:tt)* ) >> ( $($rest:tt)* )) => ({
     use $crate::lib::std::result::Result::*;
+    use $crate::lib::nom::{Convert,Err};
 
     match sep!($i, $separator, $submac!($($args)*)) {
-      Err(e) => Err(e),
+      Err(e) => Err(Err::convert(e)),
       Ok((i,_))     => {
         Ok((i, ( $($rest)* )))
       },
@@ -339,14 +643,15 @@ macro_rules! do_parse_sep (
   });
 
   (__impl $i:expr, $separator:path, $field:ident : $e:ident >> ( $($rest:tt)* )) => (
-    do_parse_sep!(__impl $i, $separator, $field: nom_call!($e) >> ( $($rest)* ) );
+    do_parse_sep!(__impl $i, $separator, $field: nom_call!($e) >> ( $($rest)* ) )
   );
 
   (__impl $i:expr, $separator:path, $field:ident : $submac:ident!( $($args:tt)* ) >> ( $($rest:tt)* )) => ({
     use $crate::lib::std::result::Result::*;
+    use $crate::lib::nom::{Convert,Err};
 
     match sep!($i, $separator, $submac!($($args)*)) {
-      Err(e) => Err(e),
+      Err(e) => Err(Err::convert(e)),
       Ok((i,o))     => {
         let $field = o;
         Ok((i, ( $($rest)* )))
@@ -408,10 +713,10 @@ macro_rules! permutation_sep (
 #[macro_export(local_inner_macros)]
 macro_rules! permutation_iterator_sep (
   ($it:tt,$i:expr, $separator:path, $all_done:expr, $needed:expr, $res:expr, $e:ident?, $($rest:tt)*) => (
-    permutation_iterator_sep!($it, $i, $separator, $all_done, $needed, $res, nom_call!($e), $($rest)*);
+    permutation_iterator_sep!($it, $i, $separator, $all_done, $needed, $res, nom_call!($e), $($rest)*)
   );
   ($it:tt,$i:expr, $separator:path, $all_done:expr, $needed:expr, $res:expr, $e:ident, $($rest:tt)*) => (
-    permutation_iterator_sep!($it, $i, $separator, $all_done, $needed, $res, nom_call!($e), $($rest)*);
+    permutation_iterator_sep!($it, $i, $separator, $all_done, $needed, $res, nom_call!($e), $($rest)*)
   );
 
   ($it:tt, $i:expr, $separator:path, $all_done:expr, $needed:expr, $res:expr, $submac:ident!( $($args:tt)* )?, $($rest:tt)*) => ({
@@ -441,10 +746,10 @@ macro_rules! permutation_iterator_sep (
   });
 
   ($it:tt,$i:expr, $separator:path, $all_done:expr, $needed:expr, $res:expr, $e:ident?) => (
-    permutation_iterator_sep!($it, $i, $separator, $all_done, $res, nom_call!($e));
+    permutation_iterator_sep!($it, $i, $separator, $all_done, $needed, $res, nom_call!($e))
   );
   ($it:tt,$i:expr, $separator:path, $all_done:expr, $needed:expr, $res:expr, $e:ident) => (
-    permutation_iterator_sep!($it, $i, $separator, $all_done, $res, nom_call!($e));
+    permutation_iterator_sep!($it, $i, $separator, $all_done, $needed, $res, nom_call!($e))
   );
 
   ($it:tt, $i:expr, $separator:path, $all_done:expr, $needed:expr, $res:expr, $submac:ident!( $($args:tt)* )?) => ({
@@ -473,11 +778,69 @@ macro_rules! permutation_iterator_sep (
   });
 );
 
+#[doc(hidden)]
+#[macro_export(local_inner_macros)]
+macro_rules! keyword_set_sep (
+  ($i:expr, $separator:path, $($submac:ident!( $($args:tt)* )),+) => (
+    {
+      use $crate::lib::std::result::Result::*;
+
+      match permutation_sep!($i, $separator, $($submac!($($args)*)?),+) {
+        Err(e)      => Err(e),
+        Ok((i,res)) => Ok((i, keyword_set_flags!(0, res, [], $($submac!($($args)*)),+))),
+      }
+    }
+  );
+);
+
+#[doc(hidden)]
+#[macro_export(local_inner_macros)]
+macro_rules! opt_prefixes_sep (
+  (__impl $i:expr, $separator:path, [$($flags:expr),*], [], $($main:tt)*) => (
+    {
+      use $crate::lib::std::result::Result::*;
+
+      match sep!($i, $separator, $($main)*) {
+        Err(e)    => Err(e),
+        Ok((i,o)) => Ok((i, ([$($flags),*], o))),
+      }
+    }
+  );
+  (__impl $i:expr, $separator:path, [$($flags:expr),*],
+    [$submac:ident!( $($args:tt)* ) $(, $rest:ident!( $($rest_args:tt)* ))*], $($main:tt)*) => (
+    {
+      use $crate::lib::std::result::Result::*;
+
+      match opt_sep!($i, $separator, $submac!($($args)*)) {
+        Err(e)    => Err(e),
+        Ok((i,o)) => {
+          let flag = o.is_some();
+          opt_prefixes_sep!(__impl i, $separator, [$($flags,)* flag], [$($rest!($($rest_args)*)),*], $($main)*)
+        }
+      }
+    }
+  );
+  ($i:expr, $separator:path, [$($prefixes:tt)*], $($main:tt)*) => (
+    opt_prefixes_sep!(__impl $i, $separator, [], [$($prefixes)*], $($main)*)
+  );
+);
+
+#[doc(hidden)]
+#[macro_export(local_inner_macros)]
+macro_rules! keyword_set_flags (
+  ($it:tt, $res:expr, [$($flags:expr),*], $submac:ident!( $($args:tt)* ), $($rest:tt)+) => (
+    succ!($it, keyword_set_flags!($res, [$($flags,)* acc!($it, $res).is_some()], $($rest)+))
+  );
+  ($it:tt, $res:expr, [$($flags:expr),*], $submac:ident!( $($args:tt)* )) => (
+    [$($flags,)* acc!($it, $res).is_some()]
+  );
+);
+
 #[doc(hidden)]
 #[macro_export(local_inner_macros)]
 macro_rules! alt_sep (
   (__impl $i:expr, $separator:path, $e:path | $($rest:tt)*) => (
-    alt_sep!(__impl $i, $separator, nom_call!($e) | $($rest)*);
+    alt_sep!(__impl $i, $separator, nom_call!($e) | $($rest)*)
   );
 
   (__impl $i:expr, $separator:path, $subrule:ident!( $($args:tt)*) | $($rest:tt)*) => (
@@ -510,11 +873,11 @@ macro_rules! alt_sep (
   );
 
   (__impl $i:expr, $separator:path, $e:path => { $gen:expr } | $($rest:tt)*) => (
-    alt_sep!(__impl $i, $separator, nom_call!($e) => { $gen } | $($rest)*);
+    alt_sep!(__impl $i, $separator, nom_call!($e) => { $gen } | $($rest)*)
   );
 
   (__impl $i:expr, $separator:path, $e:path => { $gen:expr }) => (
-    alt_sep!(__impl $i, $separator, nom_call!($e) => { $gen });
+    alt_sep!(__impl $i, $separator, nom_call!($e) => { $gen })
   );
 
   (__impl $i:expr, $separator:path, $subrule:ident!( $($args:tt)* ) => { $gen:expr }) => (
@@ -536,7 +899,7 @@ macro_rules! alt_sep (
   );
 
   (__impl $i:expr, $separator:path, $e:path) => (
-    alt_sep!(__impl $i, $separator, nom_call!($e));
+    alt_sep!(__impl $i, $separator, nom_call!($e))
   );
 
   (__impl $i:expr, $separator:path, $subrule:ident!( $($args:tt)*)) => (
@@ -578,18 +941,111 @@ macro_rules! alt_sep (
   );
 );
 
+#[doc(hidden)]
+#[macro_export(local_inner_macros)]
+macro_rules! alt_into_sep (
+  (__impl $i:expr, $separator:path, [$($done:tt)*], $e:path | $($rest:tt)+) => (
+    alt_into_sep!(__impl $i, $separator, [$($done)* nom_call!($e) => { $crate::lib::std::convert::Into::into } |], $($rest)+)
+  );
+  (__impl $i:expr, $separator:path, [$($done:tt)*], $subrule:ident!( $($args:tt)* ) | $($rest:tt)+) => (
+    alt_into_sep!(__impl $i, $separator, [$($done)* $subrule!($($args)*) => { $crate::lib::std::convert::Into::into } |], $($rest)+)
+  );
+  (__impl $i:expr, $separator:path, [$($done:tt)*], $e:path) => (
+    alt_sep!($i, $separator, $($done)* nom_call!($e) => { $crate::lib::std::convert::Into::into })
+  );
+  (__impl $i:expr, $separator:path, [$($done:tt)*], $subrule:ident!( $($args:tt)* )) => (
+    alt_sep!($i, $separator, $($done)* $subrule!($($args)*) => { $crate::lib::std::convert::Into::into })
+  );
+  ($i:expr, $separator:path, $($rest:tt)*) => (
+    alt_into_sep!(__impl $i, $separator, [], $($rest)*)
+  );
+);
+
+/// the branches are numbered during the expansion, from 0
+#[doc(hidden)]
+#[macro_export(local_inner_macros)]
+macro_rules! alt_tagged_sep (
+  (__impl $i:expr, $separator:path, $index:expr, [$($done:tt)*], $e:path | $($rest:tt)+) => (
+    alt_tagged_sep!(__impl $i, $separator, $index + 1, [$($done)* nom_call!($e) => { |o| ($index, o) } |], $($rest)+)
+  );
+  (__impl $i:expr, $separator:path, $index:expr, [$($done:tt)*], $subrule:ident!( $($args:tt)* ) | $($rest:tt)+) => (
+    alt_tagged_sep!(__impl $i, $separator, $index + 1, [$($done)* $subrule!($($args)*) => { |o| ($index, o) } |], $($rest)+)
+  );
+  (__impl $i:expr, $separator:path, $index:expr, [$($done:tt)*], $e:path) => (
+    alt_sep!($i, $separator, $($done)* nom_call!($e) => { |o| ($index, o) })
+  );
+  (__impl $i:expr, $separator:path, $index:expr, [$($done:tt)*], $subrule:ident!( $($args:tt)* )) => (
+    alt_sep!($i, $separator, $($done)* $subrule!($($args)*) => { |o| ($index, o) })
+  );
+  ($i:expr, $separator:path, $($rest:tt)*) => (
+    alt_tagged_sep!(__impl $i, $separator, 0usize, [], $($rest)*)
+  );
+);
+
+/// the first branch that matched gives the result
+#[doc(hidden)]
+#[macro_export(local_inner_macros)]
+macro_rules! alt_ambiguous_sep (
+  (__impl $i:ident, $separator:path, $res:ident, $count:ident, $e:path | $($rest:tt)*) => (
+    alt_ambiguous_sep!(__impl $i, $separator, $res, $count, nom_call!($e) | $($rest)*)
+  );
+
+  (__impl $i:ident, $separator:path, $res:ident, $count:ident, $e:path) => (
+    alt_ambiguous_sep!(__impl $i, $separator, $res, $count, nom_call!($e))
+  );
+
+  (__impl $i:ident, $separator:path, $res:ident, $count:ident, $subrule:ident!( $($args:tt)* ) | $($rest:tt)*) => (
+    alt_ambiguous_sep!(__impl $i, $separator, $res, $count, $subrule!($($args)*));
+    alt_ambiguous_sep!(__impl $i, $separator, $res, $count, $($rest)*)
+  );
+
+  (__impl $i:ident, $separator:path, $res:ident, $count:ident, $subrule:ident!( $($args:tt)* )) => (
+    match sep!($i.clone(), $separator, $subrule!($($args)*)) {
+      Ok(r)              => {
+        $count += 1;
+        if $res.is_none() {
+          $res = Some(r);
+        }
+      },
+      Err(Err::Error(_)) => {},
+      Err(e)             => break Err(e),
+    }
+  );
+
+  ($i:expr, $separator:path, $($rest:tt)*) => (
+    {
+      use $crate::lib::std::result::Result::*;
+      use $crate::lib::std::option::Option::*;
+      use $crate::lib::nom::{Err,ErrorKind};
+
+      let i_ = $i.clone();
+      let mut res = None;
+      let mut count: usize = 0;
+
+      loop {
+        alt_ambiguous_sep!(__impl i_, $separator, res, count, $($rest)*);
+
+        break match res {
+          Some((i, o)) => Ok((i, (o, count))),
+          None         => Err(Err::Error(nom_ws_error_position!(i_, ErrorKind::Alt))),
+        };
+      }
+    }
+  );
+);
+
 #[doc(hidden)]
 #[macro_export(local_inner_macros)]
 macro_rules! alt_complete_sep (
   ($i:expr, $separator:path, $e:path | $($rest:tt)*) => (
-    alt_complete_sep!($i, $separator, complete!(nom_call!($e)) | $($rest)*);
+    alt_complete_sep!($i, $separator, nom_complete!(nom_call!($e)) | $($rest)*)
   );
 
   ($i:expr, $separator:path, $subrule:ident!( $($args:tt)*) | $($rest:tt)*) => (
     {
       use $crate::lib::std::result::Result::*;
 
-      let res = complete!($i, sep!($separator, $subrule!($($args)*)));
+      let res = nom_complete!($i, sep!($separator, $subrule!($($args)*)));
       match res {
         Ok((_,_)) => res,
         _ => alt_complete_sep!($i, $separator, $($rest)*),
@@ -602,7 +1058,7 @@ macro_rules! alt_complete_sep (
       use $crate::lib::std::result::Result::*;
       use $crate::lib::nom::{Err,Needed,IResult};
 
-      match complete!($i, sep!($separator, $subrule!($($args)*))) {
+      match nom_complete!($i, sep!($separator, $subrule!($($args)*))) {
         Ok((i,o)) => Ok((i,$gen(o))),
         _ => alt_complete_sep!($i, $separator, $($rest)*),
       }
@@ -610,25 +1066,25 @@ macro_rules! alt_complete_sep (
   );
 
   ($i:expr, $separator:path, $e:path => { $gen:expr } | $($rest:tt)*) => (
-    alt_complete_sep!($i, $separator, complete!(nom_call!($e)) => { $gen } | $($rest)*);
+    alt_complete_sep!($i, $separator, nom_complete!(nom_call!($e)) => { $gen } | $($rest)*)
   );
 
   // Tail (non-recursive) rules
 
   ($i:expr, $separator:path, $e:path => { $gen:expr }) => (
-    alt_complete_sep!($i, $separator, nom_call!($e) => { $gen });
+    alt_complete_sep!($i, $separator, nom_call!($e) => { $gen })
   );
 
   ($i:expr, $separator:path, $subrule:ident!( $($args:tt)* ) => { $gen:expr }) => (
-    alt_sep!(__impl $i, $separator, complete!($subrule!($($args)*)) => { $gen })
+    alt_sep!(__impl $i, $separator, nom_complete!($subrule!($($args)*)) => { $gen })
   );
 
   ($i:expr, $separator:path, $e:path) => (
-    alt_complete_sep!($i, $separator, nom_call!($e));
+    alt_complete_sep!($i, $separator, nom_call!($e))
   );
 
   ($i:expr, $separator:path, $subrule:ident!( $($args:tt)*)) => (
-    alt_sep!(__impl $i, $separator, complete!($subrule!($($args)*)))
+    alt_sep!(__impl $i, $separator, nom_complete!($subrule!($($args)*)))
   );
 );
 
@@ -657,6 +1113,8 @@ macro_rules! switch_sep (
                   nom_ws_error_node_position!($i, $crate::lib::nom::ErrorKind::Switch, e))),
               a => a,
             }),*,
+            // unreachable if the last arm is a `_ => default` fallthrough
+            #[allow(unreachable_patterns)]
             _    => Err(Err::Error(nom_ws_error_position!($i, $crate::lib::nom::ErrorKind::Switch)))
           }
         }
@@ -680,512 +1138,6301 @@ macro_rules! switch_sep (
 #[macro_export(local_inner_macros)]
 macro_rules! separated_list_sep (
   ($i:expr, $separator:path, $submac:ident!( $($args:tt)* ), $submac2:ident!( $($args2:tt)* )) => (
-    separated_list!(
-      $i,
-      sep!($separator, $submac!($($args)*)),
-      sep!($separator, $submac2!($($args2)*))
-    )
+    {
+      use $crate::lib::std::result::Result::*;
+      use $crate::lib::nom::{Err,ErrorKind,InputLength};
+
+      let mut res   = $crate::lib::std::vec::Vec::new();
+      let mut input = $i.clone();
+
+      // get the first element
+      let input_ = input.clone();
+      match sep!(input_, $separator, $submac2!($($args2)*)) {
+        Err(Err::Error(_)) => Ok((input, res)),
+        Err(e)             => Err(e),
+        Ok((i,o))          => {
+          // a zero width first element is only accepted if a separator follows
+          let empty = i.input_len() == input.input_len();
+          if empty && sep!(i.clone(), $separator, $submac!($($args)*)).is_err() {
+            Err(Err::Error(nom_ws_error_position!(input, ErrorKind::SeparatedList)))
+          } else {
+            res.push(o);
+            input = i;
+
+            let ret;
+
+            loop {
+              // get the separator first
+              let input_ = input.clone();
+              match sep!(input_, $separator, $submac!($($args)*)) {
+                Err(Err::Error(_)) => {
+                  ret = Ok((input, res));
+                  break;
+                },
+                Err(e) => {
+                  ret = Err(e);
+                  break;
+                },
+                Ok((i2,_))     => {
+                  // the separator must make progress, since elements can be zero width
+                  if i2.input_len() == input.input_len() {
+                    ret = Ok((input, res));
+                    break;
+                  }
+
+                  // get the element next
+                  match sep!(i2, $separator, $submac2!($($args2)*)) {
+                    Err(Err::Error(_)) => {
+                      ret = Ok((input, res));
+                      break;
+                    },
+                    Err(e) => {
+                      ret = Err(e);
+                      break;
+                    },
+                    Ok((i3,o3))    => {
+                      res.push(o3);
+                      input = i3;
+                    }
+                  }
+                }
+              }
+            }
+
+            ret
+          }
+        }
+      }
+    }
   );
   ($i:expr, $separator:path, $submac:ident!( $($args:tt)* ), $g:expr) => (
-    separated_list_sep!($i, $separator, $submac!($($args)*), nom_call!($g));
+    separated_list_sep!($i, $separator, $submac!($($args)*), nom_call!($g))
   );
   ($i:expr, $separator:path, $f:expr, $submac:ident!( $($args:tt)* )) => (
-    separated_list_sep!($i, $separator, nom_call!($f), $submac!($($args)*));
+    separated_list_sep!($i, $separator, nom_call!($f), $submac!($($args)*))
   );
   ($i:expr, $separator:path, $f:expr, $g:expr) => (
-    separated_list_sep!($i, $separator, nom_call!($f), nom_call!($g));
+    separated_list_sep!($i, $separator, nom_call!($f), nom_call!($g))
   );
 );
 
-/// helper macros to build a separator parser
-///
-/// ```
-/// # #[macro_use] extern crate nom;
-/// named!(pub space, eat_separator!(&b" \t"[..]));
-/// # fn main() {}
-/// ```
+/// elements are separated by the separator, or by a comma with
+/// optional separators around it
+#[doc(hidden)]
+#[cfg(feature = "alloc")]
 #[macro_export(local_inner_macros)]
-macro_rules! eat_separator (
-  ($i:expr, $arr:expr) => (
+macro_rules! separated_list_flex_sep (
+  ($i:expr, $separator:path, $($rest:tt)*) => (
+    separated_list_sep!($i, $separator, nom_opt!(nom_tag!(",")), $($rest)*)
+  );
+);
+
+/// fails with `ErrorKind::Custom(LIST_BUDGET_ERROR)` as soon as an
+/// element beyond `$max` would be parsed
+#[doc(hidden)]
+#[cfg(feature = "alloc")]
+#[macro_export(local_inner_macros)]
+macro_rules! separated_list_budget_sep (
+  ($i:expr, __element $separator:path, $count:ident, $max:ident, $submac:ident!( $($args:tt)* )) => (
     {
-      use $crate::lib::nom::{FindToken, InputTakeAtPosition};
-      let input = $i;
-      input.split_at_position(|c| !$arr.find_token(c))
+      use $crate::lib::std::result::Result::*;
+      use $crate::lib::nom::{Err,ErrorKind};
+
+      // an element that does not parse ends the list as usual, only
+      // one more element than allowed fails
+      match sep!($i, $separator, $submac!($($args)*)) {
+        Err(e)    => Err(e),
+        Ok(_) if $count.get() == $max => {
+          Err(Err::Failure(nom_ws_error_position!($i, ErrorKind::Custom($crate::LIST_BUDGET_ERROR))))
+        },
+        Ok((i,o)) => {
+          $count.set($count.get() + 1);
+          Ok((i, o))
+        }
+      }
     }
   );
+  ($i:expr, $separator:path, $max:expr, $submac:ident!( $($args:tt)* ), $submac2:ident!( $($args2:tt)* )) => (
+    {
+      let count = $crate::lib::std::cell::Cell::new(0usize);
+      let max: usize = $max;
+
+      separated_list_sep!($i, $separator,
+        $submac!($($args)*),
+        separated_list_budget_sep!(__element $separator, count, max, $submac2!($($args2)*))
+      )
+    }
+  );
+  ($i:expr, $separator:path, $max:expr, $submac:ident!( $($args:tt)* ), $g:expr) => (
+    separated_list_budget_sep!($i, $separator, $max, $submac!($($args)*), nom_call!($g))
+  );
+  ($i:expr, $separator:path, $max:expr, $f:expr, $submac:ident!( $($args:tt)* )) => (
+    separated_list_budget_sep!($i, $separator, $max, nom_call!($f), $submac!($($args)*))
+  );
+  ($i:expr, $separator:path, $max:expr, $f:expr, $g:expr) => (
+    separated_list_budget_sep!($i, $separator, $max, nom_call!($f), nom_call!($g))
+  );
 );
 
-/// sep is the parser rewriting macro for whitespace separated formats
-///
-/// it takes as argument a space eating function and a parser tree,
-/// and will intersperse the space parser everywhere
-///
-/// ```ignore
-/// #[macro_export(local_inner_macros)]
-/// macro_rules! ws (
-///   ($i:expr, $($args:tt)*) => (
-///     {
-///       use sp;
-///       sep!($i, sp, $($args)*)
-///     }
-///   )
-/// );
-/// ```
+/// the offsets are computed from the input before the leading separator
+#[doc(hidden)]
+#[cfg(feature = "alloc")]
 #[macro_export(local_inner_macros)]
-macro_rules! sep (
-  ($i:expr,  $separator:path, tuple ! ($($rest:tt)*) ) => {
-    tuple_sep!($i, $separator, (), $($rest)*)
-  };
-  ($i:expr,  $separator:path, pair ! ($($rest:tt)*) ) => {
-    wrap_sep!($i,
-      $separator,
-      pair_sep!($separator, $($rest)*)
-    )
-  };
-  ($i:expr,  $separator:path, delimited ! ($($rest:tt)*) ) => {
-    wrap_sep!($i,
-      $separator,
-      delimited_sep!($separator, $($rest)*)
-    )
-  };
-  ($i:expr,  $separator:path, separated_pair ! ($($rest:tt)*) ) => {
-    wrap_sep!($i,
-      $separator,
-      separated_pair_sep!($separator, $($rest)*)
-    )
-  };
-  ($i:expr,  $separator:path, preceded ! ($($rest:tt)*) ) => {
-    wrap_sep!($i,
-      $separator,
-      preceded_sep!($separator, $($rest)*)
-    )
-  };
-  ($i:expr,  $separator:path, terminated ! ($($rest:tt)*) ) => {
-    wrap_sep!($i,
-      $separator,
-      terminated_sep!($separator, $($rest)*)
-    )
-  };
-  ($i:expr,  $separator:path, do_parse ! ($($rest:tt)*) ) => {
-    wrap_sep!($i,
-      $separator,
-      do_parse_sep!($separator, $($rest)*)
-    )
-  };
-  ($i:expr,  $separator:path, permutation ! ($($rest:tt)*) ) => {
-    wrap_sep!($i,
-      $separator,
-      permutation_sep!($separator, $($rest)*)
-    )
-  };
-  ($i:expr,  $separator:path, alt ! ($($rest:tt)*) ) => {
-    wrap_sep!($i,
-      $separator,
-      alt_sep!($separator, $($rest)*)
-    )
-  };
-  ($i:expr,  $separator:path, alt_complete ! ($($rest:tt)*) ) => {
-    wrap_sep!($i,
-      $separator,
-      alt_complete_sep!($separator, $($rest)*)
-    )
-  };
-  ($i:expr,  $separator:path, switch ! ($($rest:tt)*) ) => {
-    wrap_sep!($i,
-      $separator,
-      switch_sep!($separator, $($rest)*)
-    )
-  };
-  ($i:expr,  $separator:path, separated_list ! ($($rest:tt)*) ) => {
-    wrap_sep!($i,
-      $separator,
-      separated_list_sep!($separator, $($rest)*)
-    )
-  };
-  ($i:expr,  $separator:path, many0 ! ($($rest:tt)*) ) => {
-    many0!($i, wrap_sep!($separator, $($rest)*))
-  };
-  ($i:expr,  $separator:path, many1 ! ($($rest:tt)*) ) => {
-    many1!($i, wrap_sep!($separator, $($rest)*))
-  };
-  ($i:expr, $separator:path, return_error!( $($args:tt)* )) => {
-    return_error!($i, wrap_sep!($separator, $($args)*))
-  };
-//FIXME: missing separated_nonempty_list,
-// many_till, many_m_n, count, count_fixed, fold_many0, fold_many1,
-// fold_many_m_n
-  ($i:expr, $separator:path, $submac:ident!( $($args:tt)* )) => {
-    wrap_sep!($i, $separator, $submac!($($args)*))
-  };
-  ($i:expr, $separator:path, $f:expr) => {
-    wrap_sep!($i, $separator, nom_call!($f))
-  };
+macro_rules! separated_list_spanned_sep (
+  ($i:expr, __element $separator:path, $original:ident, $submac:ident!( $($args:tt)* )) => (
+    {
+      use $crate::lib::std::result::Result::*;
+      use $crate::lib::nom::Offset;
+
+      let i_ = $i.clone();
+      let offset = $original.offset(&i_);
+      match sep!(i_, $separator, $submac!($($args)*)) {
+        Err(e)    => Err(e),
+        Ok((i,o)) => Ok((i, (offset, o)))
+      }
+    }
+  );
+  ($i:expr, $separator:path, $original:expr, $submac:ident!( $($args:tt)* ), $submac2:ident!( $($args2:tt)* )) => (
+    {
+      let original = &$original;
+
+      separated_list_sep!($i, $separator,
+        $submac!($($args)*),
+        separated_list_spanned_sep!(__element $separator, original, $submac2!($($args2)*))
+      )
+    }
+  );
+  ($i:expr, $separator:path, $original:expr, $submac:ident!( $($args:tt)* ), $g:expr) => (
+    separated_list_spanned_sep!($i, $separator, $original, $submac!($($args)*), nom_call!($g))
+  );
+  ($i:expr, $separator:path, $original:expr, $f:expr, $submac:ident!( $($args:tt)* )) => (
+    separated_list_spanned_sep!($i, $separator, $original, nom_call!($f), $submac!($($args)*))
+  );
+  ($i:expr, $separator:path, $original:expr, $f:expr, $g:expr) => (
+    separated_list_spanned_sep!($i, $separator, $original, nom_call!($f), nom_call!($g))
+  );
 );
 
-use lib::nom::IResult;
-use lib::nom::{AsChar, FindToken, InputTakeAtPosition};
-#[allow(unused_imports)]
-pub fn sp<'a, T>(input: T) -> IResult<T, T>
-where
-  T: InputTakeAtPosition,
-  <T as InputTakeAtPosition>::Item: AsChar + Clone,
-  &'a str: FindToken<<T as InputTakeAtPosition>::Item>,
-{
-  input.split_at_position(|item| {
-    let c = item.clone().as_char();
-    !(c == ' ' || c == '\t' || c == '\r' || c == '\n')
-  })
-  //this could be written as followed, but not using FindToken is faster
-  //eat_separator!(input, " \t\r\n")
-}
+/// duplicate elements are silently ignored
+#[doc(hidden)]
+#[cfg(feature = "std")]
+#[macro_export(local_inner_macros)]
+macro_rules! separated_set_sep (
+  ($i:expr, $separator:path, $($rest:tt)*) => (
+    {
+      use $crate::lib::std::result::Result::*;
+      use $crate::lib::std::collections::HashSet;
 
-/// `ws!(I -> IResult<I,O>) => I -> IResult<I, O>`
-///
-/// transforms a parser to automatically consume
-/// whitespace between each token. By default,
-/// it takes the following characters: `" \t\r\n"`.
-///
-/// If you need a whitespace parser consuming a
-/// different set of characters, you can make
-/// your own by reusing the `sep!` combinator.
-///
-/// To use `ws!`, pass your parser as argument:
-///
-/// ```
-/// # #[macro_use] extern crate nom;
-/// # fn main() {
-/// named!(tuple<&[u8], (&[u8], &[u8]) >,
-///   ws!(tuple!( take!(3), tag!("de") ))
-/// );
-///
-/// assert_eq!(
-///   tuple(&b" \t abc de fg"[..]),
-///  Ok((&b"fg"[..], (&b"abc"[..], &b"de"[..])))
-/// );
-/// # }
-/// ```
-///
+      match separated_list_sep!($i, $separator, $($rest)*) {
+        Err(e)         => Err(e),
+        Ok((i, list))  => Ok((i, list.into_iter().collect::<HashSet<_>>()))
+      }
+    }
+  );
+);
+
+#[doc(hidden)]
+#[cfg(feature = "alloc")]
 #[macro_export(local_inner_macros)]
-macro_rules! ws (
-  ($i:expr, $($args:tt)*) => (
+macro_rules! separated_list_keep_sep_sep (
+  ($i:expr, $separator:path, $submac:ident!( $($args:tt)* ), $submac2:ident!( $($args2:tt)* )) => (
     {
-      use $crate::sp;
-      use $crate::lib::nom::Convert;
-      use $crate::lib::nom::Err;
       use $crate::lib::std::result::Result::*;
+      use $crate::lib::std::option::Option::*;
+      use $crate::lib::std::vec::Vec;
+      use $crate::lib::nom::{Err,InputLength};
 
-      match sep!($i, sp, $($args)*) {
-        Err(e) => Err(e),
-        Ok((i1,o))    => {
-          match (sp)(i1) {
-            Err(e) => Err(Err::convert(e)),
-            Ok((i2,_))    => Ok((i2, o))
+      let mut elements   = Vec::new();
+      let mut separators = Vec::new();
+      let i_ = $i.clone();
+      match sep!(i_.clone(), $separator, $submac2!($($args2)*)) {
+        Err(Err::Error(_)) => Ok((i_, (elements, separators))),
+        Err(e)             => Err(e),
+        Ok((i1,o))         => {
+          elements.push(o);
+
+          let mut input = i1;
+          let mut error = None;
+          loop {
+            let len = input.input_len();
+            match sep!(input.clone(), $separator, $submac!($($args)*)) {
+              Err(Err::Error(_)) => break,
+              Err(e)             => {
+                error = Some(e);
+                break;
+              },
+              Ok((i2,s))         => match sep!(i2, $separator, $submac2!($($args2)*)) {
+                Err(Err::Error(_)) => break,
+                Err(e)             => {
+                  error = Some(e);
+                  break;
+                },
+                Ok((i3,o))         => {
+                  // do not allow parsers that do not consume input (causes infinite loops)
+                  if i3.input_len() == len {
+                    break;
+                  }
+                  separators.push(s);
+                  elements.push(o);
+                  input = i3;
+                }
+              }
+            }
+          }
+
+          match error {
+            Some(e) => Err(e),
+            None    => Ok((input, (elements, separators))),
           }
         }
       }
     }
-  )
+  );
+  ($i:expr, $separator:path, $submac:ident!( $($args:tt)* ), $g:expr) => (
+    separated_list_keep_sep_sep!($i, $separator, $submac!($($args)*), nom_call!($g))
+  );
+  ($i:expr, $separator:path, $f:expr, $submac:ident!( $($args:tt)* )) => (
+    separated_list_keep_sep_sep!($i, $separator, nom_call!($f), $submac!($($args)*))
+  );
+  ($i:expr, $separator:path, $f:expr, $g:expr) => (
+    separated_list_keep_sep_sep!($i, $separator, nom_call!($f), nom_call!($g))
+  );
 );
 
-#[cfg(test)]
-#[allow(dead_code)]
-mod tests {
-  #[cfg(feature = "alloc")]
-  use lib::std::string::{String, ToString};
-  use nom::{Err, IResult, Needed};
-  use super::sp;
-  use nom::ErrorKind;
-  use nom::types::CompleteStr;
+/// the leading separator is consumed by `sep!` before, and the
+/// child parsers like `many0!` do not consume the trailing one,
+/// so the result spans from the start of the first token to the
+/// end of the last one, including the whitespace between them
+#[doc(hidden)]
+#[macro_export(local_inner_macros)]
+macro_rules! recognize_sep (
+  ($i:expr, $separator:path, $submac:ident!( $($args:tt)* )) => (
+    {
+      use $crate::lib::std::result::Result::*;
+      use $crate::lib::nom::{InputTake,Offset};
 
-  #[test]
-  fn spaaaaace() {
-    assert_eq!(sp(&b" \t abc "[..]), Ok((&b"abc "[..], &b" \t "[..])));
+      let i_ = $i.clone();
+      match sep!(i_.clone(), $separator, $submac!($($args)*)) {
+        Err(e)    => Err(e),
+        Ok((i,_)) => {
+          let consumed = i_.take(i_.offset(&i));
+          Ok((i, consumed))
+        }
+      }
+    }
+  );
+  ($i:expr, $separator:path, $f:expr) => (
+    recognize_sep!($i, $separator, nom_call!($f))
+  );
+);
+
+/// like `recognize_sep!`, but what the separator would consume at the
+/// end of the result is trimmed. It is still consumed
+#[doc(hidden)]
+#[macro_export(local_inner_macros)]
+macro_rules! recognize_trim_sep (
+  ($i:expr, $separator:path, $submac:ident!( $($args:tt)* )) => (
+    {
+      use $crate::lib::std::result::Result::*;
+      use $crate::lib::nom::{Err,InputIter,InputLength,InputTake,Offset,Slice};
+
+      let i_ = $i.clone();
+      match sep!(i_.clone(), $separator, $submac!($($args)*)) {
+        Err(e)    => Err(e),
+        Ok((i,_)) => {
+          let consumed = i_.take(i_.offset(&i));
+
+          // the result ends at the first position from which the separator
+          // consumes everything. On partial input, it asks for more instead.
+          // Runs the separator consumed before some content are skipped
+          let len = consumed.input_len();
+          let mut end  = len;
+          let mut skip = 0;
+          for (idx, _) in consumed.iter_indices() {
+            if idx < skip {
+              continue;
+            }
+            match ($separator)(consumed.slice(idx..)) {
+              Ok((rest, _)) => {
+                if rest.input_len() == 0 {
+                  end = idx;
+                  break;
+                }
+                skip = len - rest.input_len();
+              },
+              Err(Err::Incomplete(_)) => {
+                end = idx;
+                break;
+              },
+              Err(_) => {},
+            }
+          }
+
+          Ok((i, consumed.take(end)))
+        }
+      }
+    }
+  );
+  ($i:expr, $separator:path, $f:expr) => (
+    recognize_trim_sep!($i, $separator, nom_call!($f))
+  );
+);
+
+/// lines are parsed until the indentation of the next non blank line
+/// is not larger than `$parent`, or the end of input. Blank lines, empty
+/// or with only spaces and tabs, are skipped inside the block, but the
+/// ones after its last line are left in the input
+#[doc(hidden)]
+#[cfg(feature = "alloc")]
+#[macro_export(local_inner_macros)]
+macro_rules! indented_block_sep (
+  ($i:expr, $separator:path, $parent:expr, $submac:ident!( $($args:tt)* )) => (
+    {
+      use $crate::lib::std::result::Result::*;
+      use $crate::lib::nom::{Convert,Err,InputLength};
+
+      let mut res   = $crate::lib::std::vec::Vec::new();
+      let mut input = $i.clone();
+      let ret;
+
+      'lines: loop {
+        let mut next = input.clone();
+        let (line, indent) = loop {
+          match $crate::inline_sp(next.clone()) {
+            Err(e) => {
+              ret = Err(Err::convert(e));
+              break 'lines;
+            },
+            Ok((line, indent)) => match $crate::line_end(line.clone()) {
+              Ok((rest, _)) if rest.input_len() < line.input_len() => next = rest,
+              _ => break (line, indent),
+            }
+          }
+        };
+
+        // dedent or end of input
+        if indent.input_len() <= $parent || line.input_len() == 0 {
+          ret = Ok((input, res));
+          break;
+        }
+
+        match sep!(line, $separator, $submac!($($args)*)) {
+          Err(e)     => {
+            ret = Err(e);
+            break;
+          },
+          Ok((i, o)) => match terminator!(i) {
+            Err(e)      => {
+              ret = Err(Err::convert(e));
+              break;
+            },
+            Ok((i2, _)) => {
+              res.push(o);
+              input = i2;
+            }
+          }
+        }
+      }
+
+      ret
+    }
+  );
+  ($i:expr, $separator:path, $parent:expr, $f:expr) => (
+    indented_block_sep!($i, $separator, $parent, nom_call!($f))
+  );
+);
+
+/// the last column extends to the end of the line, and can contain
+/// whitespace, but not its trailing spaces and tabs
+#[doc(hidden)]
+#[cfg(feature = "alloc")]
+#[macro_export(local_inner_macros)]
+macro_rules! columns_sep (
+  ($i:expr, $separator:path, $n:expr) => (
+    {
+      use $crate::lib::std::result::Result::*;
+      use $crate::lib::std::option::Option::*;
+      use $crate::lib::nom::{AsChar,Convert,Err,ErrorKind,InputIter,InputLength,InputTake,Slice};
+
+      let mut res   = $crate::lib::std::vec::Vec::with_capacity($n);
+      let mut input = $i.clone();
+      let mut error = None;
+
+      for _ in 1..$n {
+        // the column ends where the separator starts consuming, and
+        // cannot go past the end of the line
+        let len = input.input_len();
+        let mut end = len;
+        let mut eol = None;
+        for (idx, c) in input.iter_indices() {
+          let c = c.as_char();
+          if c == '\n' || c == '\r' {
+            eol = Some(idx);
+            break;
+          }
+          if let Ok((rest, _)) = ($separator)(input.slice(idx..)) {
+            if rest.input_len() < len - idx {
+              end = idx;
+              break;
+            }
+          }
+        }
+
+        if let Some(idx) = eol {
+          error = Some(Err::Error(nom_ws_error_position!(input.slice(idx..), ErrorKind::Count)));
+          break;
+        }
+        if end == 0 || end == len {
+          error = Some(Err::Error(nom_ws_error_position!(input.clone(), ErrorKind::Count)));
+          break;
+        }
+
+        let (rest, column) = input.take_split(end);
+        res.push(column);
+        match ($separator)(rest) {
+          Ok((i, _)) => input = i,
+          Err(e)     => {
+            error = Some(Err::convert(e));
+            break;
+          },
+        }
+      }
+
+      match error {
+        Some(e) => Err(e),
+        None    => {
+          let mut end = 0;
+          for (idx, c) in input.iter_indices() {
+            let c = c.as_char();
+            if c == '\n' || c == '\r' {
+              break;
+            }
+            if c != ' ' && c != '\t' {
+              end = idx + c.len();
+            }
+          }
+
+          if end == 0 {
+            Err(Err::Error(nom_ws_error_position!(input, ErrorKind::Count)))
+          } else {
+            let (rest, column) = input.take_split(end);
+            res.push(column);
+            Ok((rest, res))
+          }
+        }
+      }
+    }
+  );
+);
+
+#[doc(hidden)]
+#[cfg(feature = "alloc")]
+#[macro_export(local_inner_macros)]
+macro_rules! kv_list_sep (
+  (__impl $i:expr, $separator:path, [$($done:tt)*], $submac:ident!( $($args:tt)* ), $($rest:tt)+) => (
+    kv_list_sep!(__impl $i, $separator, [$($done)* $submac!($($args)*),], $($rest)+)
+  );
+  (__impl $i:expr, $separator:path, [$($done:tt)*], $f:expr, $($rest:tt)+) => (
+    kv_list_sep!(__impl $i, $separator, [$($done)* nom_call!($f),], $($rest)+)
+  );
+  (__impl $i:expr, $separator:path,
+    [$list:ident!( $($list_args:tt)* ), $kv:ident!( $($kv_args:tt)* ), $key:ident!( $($key_args:tt)* ),],
+    $value:ident!( $($value_args:tt)* )) => (
+    separated_list_sep!($i, $separator,
+      $list!($($list_args)*),
+      separated_pair!($key!($($key_args)*), $kv!($($kv_args)*), $value!($($value_args)*))
+    )
+  );
+  (__impl $i:expr, $separator:path, [$($done:tt)*], $f:expr) => (
+    kv_list_sep!(__impl $i, $separator, [$($done)*], nom_call!($f))
+  );
+  ($i:expr, $separator:path, $($rest:tt)*) => (
+    kv_list_sep!(__impl $i, $separator, [], $($rest)*)
+  );
+);
+
+/// elements are separated by the separator itself, so it must consume
+/// something between them
+#[doc(hidden)]
+#[cfg(feature = "alloc")]
+#[macro_export(local_inner_macros)]
+macro_rules! flags_and_kv_sep (
+  (__impl $i:expr, $separator:path, [$($done:tt)*], $submac:ident!( $($args:tt)* ), $($rest:tt)+) => (
+    flags_and_kv_sep!(__impl $i, $separator, [$($done)* $submac!($($args)*),], $($rest)+)
+  );
+  (__impl $i:expr, $separator:path, [$($done:tt)*], $f:expr, $($rest:tt)+) => (
+    flags_and_kv_sep!(__impl $i, $separator, [$($done)* nom_call!($f),], $($rest)+)
+  );
+  (__impl $i:expr, $separator:path,
+    [$key:ident!( $($key_args:tt)* ), $kv:ident!( $($kv_args:tt)* ),],
+    $value:ident!( $($value_args:tt)* )) => (
+    separated_list_sep!($i, $separator,
+      nom_call!($separator),
+      alt!(
+        separated_pair!($key!($($key_args)*), $kv!($($kv_args)*), $value!($($value_args)*)) => {
+          |(k, v)| (k, $crate::lib::std::option::Option::Some(v))
+        } |
+        $key!($($key_args)*) => { |k| (k, $crate::lib::std::option::Option::None) }
+      )
+    )
+  );
+  (__impl $i:expr, $separator:path, [$($done:tt)*], $f:expr) => (
+    flags_and_kv_sep!(__impl $i, $separator, [$($done)*], nom_call!($f))
+  );
+  ($i:expr, $separator:path, $($rest:tt)*) => (
+    flags_and_kv_sep!(__impl $i, $separator, [], $($rest)*)
+  );
+);
+
+#[doc(hidden)]
+#[cfg(feature = "alloc")]
+#[macro_export(local_inner_macros)]
+macro_rules! balanced_sep (
+  (__impl $i:expr, $separator:path, [$($done:tt)*], $submac:ident!( $($args:tt)* ), $($rest:tt)+) => (
+    balanced_sep!(__impl $i, $separator, [$($done)* $submac!($($args)*),], $($rest)+)
+  );
+  (__impl $i:expr, $separator:path, [$($done:tt)*], $f:expr, $($rest:tt)+) => (
+    balanced_sep!(__impl $i, $separator, [$($done)* nom_call!($f),], $($rest)+)
+  );
+  (__impl $i:expr, $separator:path,
+    [$open:ident!( $($open_args:tt)* ), $close:ident!( $($close_args:tt)* ),],
+    $element:ident!( $($element_args:tt)* )) => (
+    $crate::balanced($i,
+      &|i| sep!(i, $separator, $open!($($open_args)*)),
+      &|i| sep!(i, $separator, $close!($($close_args)*)),
+      &|i| sep!(i, $separator, $element!($($element_args)*))
+    )
+  );
+  (__impl $i:expr, $separator:path, [$($done:tt)*], $f:expr) => (
+    balanced_sep!(__impl $i, $separator, [$($done)*], nom_call!($f))
+  );
+  ($i:expr, $separator:path, $($rest:tt)*) => (
+    balanced_sep!(__impl $i, $separator, [], $($rest)*)
+  );
+);
+
+#[doc(hidden)]
+#[cfg(feature = "alloc")]
+#[macro_export(local_inner_macros)]
+macro_rules! bracketed_list_sep (
+  (__impl $i:expr, $separator:path, [$($done:tt)*], $submac:ident!( $($args:tt)* ), $($rest:tt)+) => (
+    bracketed_list_sep!(__impl $i, $separator, [$($done)* $submac!($($args)*),], $($rest)+)
+  );
+  (__impl $i:expr, $separator:path, [$($done:tt)*], $f:expr, $($rest:tt)+) => (
+    bracketed_list_sep!(__impl $i, $separator, [$($done)* nom_call!($f),], $($rest)+)
+  );
+  (__impl $i:expr, $separator:path,
+    [$open:ident!( $($open_args:tt)* ), $list:ident!( $($list_args:tt)* ), $element:ident!( $($element_args:tt)* ),],
+    $close:ident!( $($close_args:tt)* )) => (
+    {
+      use $crate::lib::std::result::Result::*;
+
+      match sep!($i, $separator, $open!($($open_args)*)) {
+        Err(e)      => Err(e),
+        Ok((i1, _)) => match separated_list_sep!(i1, $separator, $list!($($list_args)*), $element!($($element_args)*)) {
+          Err(e)      => Err(e),
+          Ok((i2, o)) => match sep!(i2, $separator, $close!($($close_args)*)) {
+            Err(e)      => Err(e),
+            Ok((i3, _)) => Ok((i3, o))
+          }
+        }
+      }
+    }
+  );
+  (__impl $i:expr, $separator:path, [$($done:tt)*], $f:expr) => (
+    bracketed_list_sep!(__impl $i, $separator, [$($done)*], nom_call!($f))
+  );
+  ($i:expr, $separator:path, $($rest:tt)*) => (
+    bracketed_list_sep!(__impl $i, $separator, [], $($rest)*)
+  );
+);
+
+/// like `many_till_peek_sep!`, but the terminator is consumed
+#[doc(hidden)]
+#[cfg(feature = "alloc")]
+#[macro_export(local_inner_macros)]
+macro_rules! list_until_sep (
+  ($i:expr, $separator:path, $submac:ident!( $($args:tt)* ), $submac2:ident!( $($args2:tt)* )) => (
+    many_till_peek_sep!(__impl $i, $separator, consume, $submac!($($args)*), $submac2!($($args2)*))
+  );
+  ($i:expr, $separator:path, $submac:ident!( $($args:tt)* ), $g:expr) => (
+    list_until_sep!($i, $separator, $submac!($($args)*), nom_call!($g))
+  );
+  ($i:expr, $separator:path, $f:expr, $submac:ident!( $($args:tt)* )) => (
+    list_until_sep!($i, $separator, nom_call!($f), $submac!($($args)*))
+  );
+  ($i:expr, $separator:path, $f:expr, $g:expr) => (
+    list_until_sep!($i, $separator, nom_call!($f), nom_call!($g))
+  );
+);
+
+/// the terminator is only peeked at, and the separator before
+/// it is not consumed either. `list_until_sep!` shares the loop
+#[doc(hidden)]
+#[cfg(feature = "alloc")]
+#[macro_export(local_inner_macros)]
+macro_rules! many_till_peek_sep (
+  (__impl $i:expr, $separator:path, $end:ident, $submac:ident!( $($args:tt)* ), $submac2:ident!( $($args2:tt)* )) => (
+    {
+      use $crate::lib::std::result::Result::*;
+      use $crate::lib::nom::{Err,ErrorKind,InputLength};
+
+      let ret;
+      let mut res   = $crate::lib::std::vec::Vec::new();
+      let mut input = $i.clone();
+
+      loop {
+        match sep!(input.clone(), $separator, $submac2!($($args2)*)) {
+          Ok((i, _))         => {
+            ret = Ok((many_till_peek_sep!(__end $end, input, i), res));
+            break;
+          },
+          Err(Err::Error(_)) => {},
+          Err(e)             => {
+            ret = Err(e);
+            break;
+          },
+        }
+
+        let len = input.input_len();
+        match sep!(input.clone(), $separator, $submac!($($args)*)) {
+          Ok((i, o)) => {
+            if i.input_len() == len {
+              ret = Err(Err::Error(nom_ws_error_position!(input, ErrorKind::ManyTill)));
+              break;
+            }
+            res.push(o);
+            input = i;
+          },
+          Err(Err::Error(_)) => {
+            ret = Err(Err::Error(nom_ws_error_position!(input, ErrorKind::ManyTill)));
+            break;
+          },
+          Err(e)             => {
+            ret = Err(e);
+            break;
+          },
+        }
+      }
+
+      ret
+    }
+  );
+  // `list_until!` consumes the terminator, `many_till_peek!` does not
+  (__end consume, $before:expr, $after:expr) => (
+    $after
+  );
+  (__end peek, $before:expr, $after:expr) => (
+    {
+      let _ = $after;
+      $before
+    }
+  );
+  ($i:expr, $separator:path, $submac:ident!( $($args:tt)* ), $submac2:ident!( $($args2:tt)* )) => (
+    many_till_peek_sep!(__impl $i, $separator, peek, $submac!($($args)*), $submac2!($($args2)*))
+  );
+  ($i:expr, $separator:path, $submac:ident!( $($args:tt)* ), $g:expr) => (
+    many_till_peek_sep!($i, $separator, $submac!($($args)*), nom_call!($g))
+  );
+  ($i:expr, $separator:path, $f:expr, $submac:ident!( $($args:tt)* )) => (
+    many_till_peek_sep!($i, $separator, nom_call!($f), $submac!($($args)*))
+  );
+  ($i:expr, $separator:path, $f:expr, $g:expr) => (
+    many_till_peek_sep!($i, $separator, nom_call!($f), nom_call!($g))
+  );
+);
+
+/// like `many0!`, with the separator applied before each element.
+/// The progress check covers both the separator and the element:
+/// if a whole step does not consume anything, the loop stops and
+/// returns the elements collected so far
+#[doc(hidden)]
+#[cfg(feature = "alloc")]
+#[macro_export(local_inner_macros)]
+macro_rules! many0_sep (
+  ($i:expr, $separator:path, $($rest:tt)*) => (
+    {
+      use $crate::lib::std::result::Result::*;
+      use $crate::lib::nom::{Err,InputLength};
+
+      let ret;
+      let mut res   = $crate::lib::std::vec::Vec::new();
+      let mut input = $i.clone();
+
+      loop {
+        let len = input.input_len();
+        match wrap_sep!(input.clone(), $separator, $($rest)*) {
+          Ok((i, o))         => {
+            // separator and element consumed nothing, stop there
+            if i.input_len() == len {
+              ret = Ok((input, res));
+              break;
+            }
+            res.push(o);
+            input = i;
+          },
+          Err(Err::Error(_)) => {
+            ret = Ok((input, res));
+            break;
+          },
+          Err(e)             => {
+            ret = Err(e);
+            break;
+          },
+        }
+      }
+
+      ret
+    }
+  );
+);
+
+/// the separator before the first element is consumed before
+/// applying `many1!`, so that errors point at the first element.
+/// It has the same progress check as `many0_sep!`
+#[doc(hidden)]
+#[cfg(feature = "alloc")]
+#[macro_export(local_inner_macros)]
+macro_rules! many1_sep (
+  ($i:expr, __impl $separator:path, $($rest:tt)*) => (
+    {
+      use $crate::lib::std::result::Result::*;
+      use $crate::lib::nom::{Err,ErrorKind};
+
+      let i_ = $i.clone();
+      match many0_sep!(i_.clone(), $separator, $($rest)*) {
+        Err(e)                         => Err(e),
+        Ok((_, ref v)) if v.is_empty() => Err(Err::Error(nom_ws_error_position!(i_, ErrorKind::Many1))),
+        Ok((i, v))                     => Ok((i, v)),
+      }
+    }
+  );
+  ($i:expr, $separator:path, $($rest:tt)*) => (
+    wrap_sep!($i,
+      $separator,
+      many1_sep!(__impl $separator, $($rest)*)
+    )
+  );
+);
+
+/// like `many1_sep!`, but all the results must be equal, otherwise it
+/// fails with `ErrorKind::Custom(MANY_SAME_ERROR)` on the first differing
+/// element. It returns the count and the value
+#[doc(hidden)]
+#[macro_export(local_inner_macros)]
+macro_rules! many_same_sep (
+  ($i:expr, $separator:path, $submac:ident!( $($args:tt)* )) => (
+    {
+      use $crate::lib::std::result::Result::*;
+      use $crate::lib::std::option::Option::*;
+      use $crate::lib::nom::{Convert,Err,ErrorKind,InputLength};
+
+      match sep!($i, $separator, $submac!($($args)*)) {
+        Err(e)         => Err(e),
+        Ok((i1,first)) => {
+          let mut count = 1;
+          let mut input = i1;
+          let mut error = None;
+
+          loop {
+            let len = input.input_len();
+            let i_ = match ($separator)(input.clone()) {
+              Ok((i, _)) => i,
+              Err(e)     => {
+                error = Some(Err::convert(e));
+                break;
+              },
+            };
+            match sep!(i_.clone(), $separator, $submac!($($args)*)) {
+              Err(Err::Error(_)) => break,
+              Err(e)             => {
+                error = Some(e);
+                break;
+              },
+              Ok((i2,o))         => {
+                // infinite loop check: the parser must always consume
+                if i2.input_len() == len {
+                  break;
+                }
+                if o != first {
+                  error = Some(Err::Error(nom_ws_error_position!(i_, ErrorKind::Custom($crate::MANY_SAME_ERROR))));
+                  break;
+                }
+                count += 1;
+                input = i2;
+              }
+            }
+          }
+
+          match error {
+            Some(e) => Err(e),
+            None    => Ok((input, (count, first))),
+          }
+        }
+      }
+    }
+  );
+  ($i:expr, $separator:path, $f:expr) => (
+    many_same_sep!($i, $separator, nom_call!($f))
+  );
+);
+
+#[doc(hidden)]
+#[cfg(feature = "alloc")]
+#[macro_export(local_inner_macros)]
+macro_rules! many_m_n_sep (
+  ($i:expr, $separator:path, $m:expr, $n:expr, $submac:ident!( $($args:tt)* )) => (
+    {
+      let input = $i;
+      nom_many_m_n!(input, $m, $n, sep!($separator, $submac!($($args)*)))
+    }
+  );
+  ($i:expr, $separator:path, $m:expr, $n:expr, $f:expr) => (
+    many_m_n_sep!($i, $separator, $m, $n, nom_call!($f))
+  );
+);
+
+#[doc(hidden)]
+#[macro_export(local_inner_macros)]
+macro_rules! fold_many0_sep (
+  ($i:expr, $separator:path, $submac:ident!( $($args:tt)* ), $init:expr, $fold:expr) => (
+    {
+      let input = $i;
+      nom_fold_many0!(input, sep!($separator, $submac!($($args)*)), $init, $fold)
+    }
+  );
+  ($i:expr, $separator:path, $f:expr, $init:expr, $fold:expr) => (
+    fold_many0_sep!($i, $separator, nom_call!($f), $init, $fold)
+  );
+);
+
+/// the fold returns a `ControlFlow`: on `Break`, the loop stops after
+/// the current element, leaving the rest of the input unparsed
+#[doc(hidden)]
+#[macro_export(local_inner_macros)]
+macro_rules! fold_many0_while_sep (
+  ($i:expr, $separator:path, $submac:ident!( $($args:tt)* ), $init:expr, $fold:expr) => (
+    {
+      use $crate::lib::std::result::Result::*;
+      use $crate::lib::std::ops::ControlFlow;
+      use $crate::lib::nom::{Err,InputLength};
+
+      let f = $fold;
+      let mut acc = $init;
+      let mut input = $i.clone();
+      let ret;
+
+      loop {
+        let len = input.input_len();
+        match sep!(input.clone(), $separator, $submac!($($args)*)) {
+          Err(Err::Error(_)) => {
+            ret = Ok((input, acc));
+            break;
+          },
+          Err(e)             => {
+            ret = Err(e);
+            break;
+          },
+          Ok((i,o))          => {
+            // stop on zero width elements
+            if i.input_len() == len {
+              ret = Ok((input, acc));
+              break;
+            }
+
+            match f(acc, o) {
+              ControlFlow::Continue(a) => {
+                acc = a;
+                input = i;
+              },
+              ControlFlow::Break(a)    => {
+                ret = Ok((i, a));
+                break;
+              },
+            }
+          }
+        }
+      }
+
+      ret
+    }
+  );
+  ($i:expr, $separator:path, $f:expr, $init:expr, $fold:expr) => (
+    fold_many0_while_sep!($i, $separator, nom_call!($f), $init, $fold)
+  );
+);
+
+/// like `fold_many1!`, but when an element after the first one fails
+/// with `Err::Failure`, the error is `ErrorKind::Custom(index)`, with
+/// `index` the position of that element in the list, starting from 0
+#[doc(hidden)]
+#[macro_export(local_inner_macros)]
+macro_rules! fold_many1_sep (
+  ($i:expr, $separator:path, $submac:ident!( $($args:tt)* ), $init:expr, $fold:expr) => (
+    {
+      use $crate::lib::std::result::Result::*;
+      use $crate::lib::nom::{Convert,Err,ErrorKind,InputLength};
+
+      let i_ = $i.clone();
+      match sep!(i_.clone(), $separator, $submac!($($args)*)) {
+        Err(Err::Error(_))   => Err(Err::Error(nom_ws_error_position!(i_, ErrorKind::Many1))),
+        Err(Err::Failure(_)) => Err(Err::Failure(nom_ws_error_position!(i_, ErrorKind::Many1))),
+        Err(e)               => Err(e),
+        Ok((i1,o1))          => {
+          let f = $fold;
+          let mut acc = f($init, o1);
+          let mut input = i1;
+          let mut index: u32 = 1;
+          let ret;
+
+          loop {
+            let len = input.input_len();
+            let i2 = match ($separator)(input.clone()) {
+              Ok((i2, _))        => i2,
+              Err(Err::Error(_)) => {
+                ret = Ok((input, acc));
+                break;
+              },
+              Err(e)             => {
+                ret = Err(Err::convert(e));
+                break;
+              }
+            };
+
+            match sep!(i2.clone(), $separator, $submac!($($args)*)) {
+              Err(Err::Failure(e)) => {
+                ret = Err(Err::Failure(nom_ws_error_node_position!(i2, ErrorKind::Custom(index.into()), e)));
+                break;
+              },
+              Err(Err::Error(_))   => {
+                ret = Ok((input, acc));
+                break;
+              },
+              Err(e)               => {
+                ret = Err(e);
+                break;
+              },
+              Ok((i,o))            => {
+                if i.input_len() == len {
+                  ret = Ok((input, acc));
+                  break;
+                }
+                acc = f(acc, o);
+                input = i;
+                index += 1;
+              }
+            }
+          }
+
+          ret
+        }
+      }
+    }
+  );
+  ($i:expr, $separator:path, $f:expr, $init:expr, $fold:expr) => (
+    fold_many1_sep!($i, $separator, nom_call!($f), $init, $fold)
+  );
+);
+
+#[doc(hidden)]
+#[macro_export(local_inner_macros)]
+macro_rules! many0_acc_sep (
+  ($i:expr, $separator:path, $($rest:tt)*) => (
+    fold_many0_sep!($i, $separator, $($rest)*)
+  );
+);
+
+/// a `separated_list!` using the separator between the numbers, with
+/// `map_res!` converting them. The list ends before the first token that
+/// is not a number, which is left in the input with the separator before it
+#[doc(hidden)]
+#[cfg(feature = "alloc")]
+#[macro_export(local_inner_macros)]
+macro_rules! number_list_sep (
+  ($i:expr, $separator:path, $submac:ident!( $($args:tt)* )) => (
+    separated_list_sep!($i, $separator,
+      nom_call!($separator),
+      nom_map_res!($submac!($($args)*), |o| $crate::lib::nom::ParseTo::parse_to(&o).ok_or(()))
+    )
+  );
+  ($i:expr, $separator:path, $f:expr) => (
+    number_list_sep!($i, $separator, nom_call!($f))
+  );
+);
+
+/// the result `Vec` is allocated with the expected capacity
+#[doc(hidden)]
+#[cfg(feature = "alloc")]
+#[macro_export(local_inner_macros)]
+macro_rules! count_sep (
+  ($i:expr, $separator:path, $submac:ident!( $($args:tt)* ), $count:expr) => (
+    {
+      use $crate::lib::std::result::Result::*;
+      use $crate::lib::nom::{Err,ErrorKind};
+
+      let ret;
+      let count: usize = $count;
+      let i_ = $i.clone();
+      let mut input = i_.clone();
+      let mut res   = $crate::lib::std::vec::Vec::with_capacity(count);
+
+      loop {
+        if res.len() == count {
+          ret = Ok((input, res));
+          break;
+        }
+
+        match sep!(input.clone(), $separator, $submac!($($args)*)) {
+          Ok((i,o))          => {
+            res.push(o);
+            input = i;
+          },
+          Err(Err::Error(_)) => {
+            ret = Err(Err::Error(nom_ws_error_position!(i_, ErrorKind::Count)));
+            break;
+          },
+          Err(e)             => {
+            ret = Err(e);
+            break;
+          },
+        }
+      }
+
+      ret
+    }
+  );
+  ($i:expr, $separator:path, $f:expr, $count:expr) => (
+    count_sep!($i, $separator, nom_call!($f), $count)
+  );
+);
+
+#[doc(hidden)]
+#[cfg(feature = "alloc")]
+#[macro_export(local_inner_macros)]
+macro_rules! count_range_sep (
+  ($i:expr, $separator:path, $m:expr, $n:expr, $($rest:tt)*) => (
+    many_m_n_sep!($i, $separator, $m, $n, $($rest)*)
+  );
+);
+
+#[doc(hidden)]
+#[macro_export(local_inner_macros)]
+macro_rules! length_data_sep (
+  ($i:expr, $separator:path, $submac:ident!( $($args:tt)* )) => (
+    {
+      use $crate::lib::std::result::Result::*;
+      use $crate::lib::nom::{Convert,Err};
+
+      match sep!($i, $separator, $submac!($($args)*)) {
+        Err(e)     => Err(e),
+        Ok((i1, o)) => {
+          match ($separator)(i1) {
+            Err(e)      => Err(Err::convert(e)),
+            Ok((i2, _)) => {
+              match nom_take!(i2, o as usize) {
+                Err(e)       => Err(Err::convert(e)),
+                Ok((i3, o3)) => Ok((i3, o3))
+              }
+            }
+          }
+        }
+      }
+    }
+  );
+  ($i:expr, $separator:path, $f:expr) => (
+    length_data_sep!($i, $separator, nom_call!($f))
+  );
+);
+
+/// like `length_data_sep!`, but the data starts right after the count
+#[doc(hidden)]
+#[macro_export(local_inner_macros)]
+macro_rules! length_data_no_sep_sep (
+  ($i:expr, $separator:path, $submac:ident!( $($args:tt)* )) => (
+    {
+      use $crate::lib::std::result::Result::*;
+      use $crate::lib::nom::{Convert,Err};
+
+      match sep!($i, $separator, $submac!($($args)*)) {
+        Err(e)     => Err(e),
+        Ok((i1, o)) => {
+          match nom_take!(i1, o as usize) {
+            Err(e)       => Err(Err::convert(e)),
+            Ok((i2, o2)) => Ok((i2, o2))
+          }
+        }
+      }
+    }
+  );
+  ($i:expr, $separator:path, $f:expr) => (
+    length_data_no_sep_sep!($i, $separator, nom_call!($f))
+  );
+);
+
+#[doc(hidden)]
+#[macro_export(local_inner_macros)]
+macro_rules! value_sep (
+  ($i:expr, $separator:path, $res:expr, $submac:ident!( $($args:tt)* )) => (
+    {
+      use $crate::lib::std::result::Result::*;
+
+      match sep!($i, $separator, $submac!($($args)*)) {
+        Err(e)    => Err(e),
+        Ok((i,_)) => Ok((i, $res)),
+      }
+    }
+  );
+  ($i:expr, $separator:path, $res:expr, $f:expr) => (
+    value_sep!($i, $separator, $res, nom_call!($f))
+  );
+  ($i:expr, $separator:path, $res:expr) => (
+    $crate::lib::std::result::Result::Ok(($i, $res))
+  );
+);
+
+#[doc(hidden)]
+#[macro_export(local_inner_macros)]
+macro_rules! map_sep (
+  ($i:expr, $separator:path, $submac:ident!( $($args:tt)* ), $g:expr) => (
+    {
+      use $crate::lib::std::result::Result::*;
+
+      match sep!($i, $separator, $submac!($($args)*)) {
+        Err(e)    => Err(e),
+        Ok((i,o)) => Ok((i, $g(o))),
+      }
+    }
+  );
+  ($i:expr, $separator:path, $f:expr, $g:expr) => (
+    map_sep!($i, $separator, nom_call!($f), $g)
+  );
+);
+
+/// the middle parsers are tried in order, as with `alt!`
+#[doc(hidden)]
+#[macro_export(local_inner_macros)]
+macro_rules! separated_pair_alt_sep (
+  ($i:expr, $separator:path, $submac:ident!( $($args:tt)* ),
+    [$($middle:ident!( $($middle_args:tt)* )),+], $submac2:ident!( $($args2:tt)* )) => (
+    sep!($i, $separator,
+      tuple!($submac!($($args)*), alt!($($middle!($($middle_args)*))|+), $submac2!($($args2)*))
+    )
+  );
+  ($i:expr, $separator:path, $submac:ident!( $($args:tt)* ), [$($middle:tt)*], $g:expr) => (
+    separated_pair_alt_sep!($i, $separator, $submac!($($args)*), [$($middle)*], nom_call!($g))
+  );
+  ($i:expr, $separator:path, $f:expr, [$($middle:tt)*], $($rest:tt)*) => (
+    separated_pair_alt_sep!($i, $separator, nom_call!($f), [$($middle)*], $($rest)*)
+  );
+);
+
+/// fails with `ErrorKind::MapOpt` at the start of the token,
+/// after the separator, if `$reject` flags the mapped value
+#[doc(hidden)]
+#[macro_export(local_inner_macros)]
+macro_rules! map_reject_sep (
+  ($i:expr, $separator:path, $submac:ident!( $($args:tt)* ), $g:expr, $reject:expr) => (
+    {
+      use $crate::lib::std::result::Result::*;
+      use $crate::lib::nom::{Err,ErrorKind};
+
+      let i_ = $i.clone();
+      match sep!(i_.clone(), $separator, $submac!($($args)*)) {
+        Err(e)    => Err(e),
+        Ok((i,o)) => {
+          let mapped = $g(o);
+          if $reject(&mapped) {
+            Err(Err::Error(nom_ws_error_position!(i_, ErrorKind::MapOpt)))
+          } else {
+            Ok((i, mapped))
+          }
+        }
+      }
+    }
+  );
+  ($i:expr, $separator:path, $f:expr, $g:expr, $reject:expr) => (
+    map_reject_sep!($i, $separator, nom_call!($f), $g, $reject)
+  );
+);
+
+/// the child parser is applied once, and the predicate is
+/// checked on its output, so it is never reapplied on failure
+#[doc(hidden)]
+#[macro_export(local_inner_macros)]
+macro_rules! verify_sep (
+  ($i:expr, $separator:path, $submac:ident!( $($args:tt)* ), $g:expr) => (
+    {
+      use $crate::lib::std::result::Result::*;
+      use $crate::lib::nom::{Err,ErrorKind};
+
+      let i_ = $i.clone();
+      match sep!(i_.clone(), $separator, $submac!($($args)*)) {
+        Err(e)    => Err(e),
+        Ok((i,o)) => {
+          if $g(&o) {
+            Ok((i, o))
+          } else {
+            Err(Err::Error(nom_ws_error_position!(i_, ErrorKind::Verify)))
+          }
+        }
+      }
+    }
+  );
+  ($i:expr, $separator:path, $f:expr, $g:expr) => (
+    verify_sep!($i, $separator, nom_call!($f), $g)
+  );
+);
+
+#[doc(hidden)]
+#[macro_export(local_inner_macros)]
+macro_rules! map_result_sep (
+  ($i:expr, $separator:path, $submac:ident!( $($args:tt)* ), $g:expr) => (
+    {
+      use $crate::lib::std::result::Result::*;
+      use $crate::lib::nom::{Err,ErrorKind};
+
+      let i_ = $i.clone();
+      match sep!(i_.clone(), $separator, $submac!($($args)*)) {
+        Err(e)     => Err(e),
+        Ok((i,o))  => match $g(o) {
+          Ok(output) => Ok((i, output)),
+          Err(_)     => Err(Err::Error(nom_ws_error_position!(i_, ErrorKind::MapRes))),
+        }
+      }
+    }
+  );
+  ($i:expr, $separator:path, $f:expr, $g:expr) => (
+    map_result_sep!($i, $separator, nom_call!($f), $g)
+  );
+);
+
+/// the separator is only applied if the condition is true
+#[doc(hidden)]
+#[macro_export(local_inner_macros)]
+macro_rules! cond_with_error_sep (
+  ($i:expr, $separator:path, $cond:expr, $submac:ident!( $($args:tt)* )) => (
+    {
+      use $crate::lib::std::result::Result::*;
+      use $crate::lib::std::option::Option::*;
+
+      let i_ = $i.clone();
+      if $cond {
+        match sep!(i_, $separator, $submac!($($args)*)) {
+          Err(e)    => Err(e),
+          Ok((i,o)) => Ok((i, Some(o)))
+        }
+      } else {
+        let res: $crate::lib::std::result::Result<_,_> = Ok((i_, None));
+        res
+      }
+    }
+  );
+  ($i:expr, $separator:path, $cond:expr, $f:expr) => (
+    cond_with_error_sep!($i, $separator, $cond, nom_call!($f))
+  );
+);
+
+/// with an error code, fails with `ErrorKind::Custom(code)` instead
+/// of `ErrorKind::CondReduce` if the condition is false
+#[doc(hidden)]
+#[macro_export(local_inner_macros)]
+macro_rules! cond_reduce_sep (
+  ($i:expr, $separator:path, $cond:expr, $submac:ident!( $($args:tt)* )) => (
+    {
+      use $crate::lib::std::result::Result::*;
+      use $crate::lib::nom::{Err,ErrorKind};
+
+      let i_ = $i.clone();
+      if $cond {
+        sep!(i_, $separator, $submac!($($args)*))
+      } else {
+        Err(Err::Error(nom_ws_error_position!(i_, ErrorKind::CondReduce)))
+      }
+    }
+  );
+  ($i:expr, $separator:path, $cond:expr, $code:expr, $submac:ident!( $($args:tt)* )) => (
+    {
+      use $crate::lib::std::result::Result::*;
+      use $crate::lib::nom::{Err,ErrorKind};
+
+      let i_ = $i.clone();
+      if $cond {
+        sep!(i_, $separator, $submac!($($args)*))
+      } else {
+        Err(Err::Error(nom_ws_error_position!(i_, ErrorKind::Custom($code))))
+      }
+    }
+  );
+  ($i:expr, $separator:path, $cond:expr, $f:expr) => (
+    cond_reduce_sep!($i, $separator, $cond, nom_call!($f))
+  );
+  ($i:expr, $separator:path, $cond:expr, $code:expr, $f:expr) => (
+    cond_reduce_sep!($i, $separator, $cond, $code, nom_call!($f))
+  );
+);
+
+#[doc(hidden)]
+#[macro_export(local_inner_macros)]
+macro_rules! map_opt_sep (
+  ($i:expr, $separator:path, $submac:ident!( $($args:tt)* ), $g:expr) => (
+    {
+      use $crate::lib::std::result::Result::*;
+      use $crate::lib::std::option::Option::*;
+      use $crate::lib::nom::{Err,ErrorKind};
+
+      let i_ = $i.clone();
+      match sep!(i_.clone(), $separator, $submac!($($args)*)) {
+        Err(e)     => Err(e),
+        Ok((i,o))  => match $g(o) {
+          Some(output) => Ok((i, output)),
+          None         => Err(Err::Error(nom_ws_error_position!(i_, ErrorKind::MapOpt))),
+        }
+      }
+    }
+  );
+  ($i:expr, $separator:path, $f:expr, $g:expr) => (
+    map_opt_sep!($i, $separator, nom_call!($f), $g)
+  );
+);
+
+#[doc(hidden)]
+#[macro_export(local_inner_macros)]
+macro_rules! escaped_flagged_sep (
+  ($i:expr, $separator:path, $normal:ident!( $($args:tt)* ), $control_char:expr, $($rest:tt)+) => (
+    {
+      use $crate::lib::std::result::Result::*;
+      use $crate::lib::nom::{AsChar,InputIter};
+
+      match nom_escaped!($i, $normal!($($args)*), $control_char, $($rest)*) {
+        Err(e)    => Err(e),
+        Ok((i,o)) => {
+          let flag = o.position(|c| c.as_char() == $control_char).is_some();
+          Ok((i, (o, flag)))
+        }
+      }
+    }
+  );
+  ($i:expr, $separator:path, $normal:expr, $control_char:expr, $($rest:tt)+) => (
+    escaped_flagged_sep!($i, $separator, nom_call!($normal), $control_char, $($rest)*)
+  );
+);
+
+#[doc(hidden)]
+#[macro_export(local_inner_macros)]
+macro_rules! verify_input_sep (
+  ($i:expr, $separator:path, $submac:ident!( $($args:tt)* ), $g:expr) => (
+    {
+      use $crate::lib::std::result::Result::*;
+      use $crate::lib::nom::{Err,ErrorKind,InputTake,Offset};
+
+      let i_ = $i.clone();
+      match sep!(i_.clone(), $separator, $submac!($($args)*)) {
+        Err(e)     => Err(e),
+        Ok((i,o))  => {
+          let consumed = i_.take(i_.offset(&i));
+          if $g(&o, &consumed) {
+            Ok((i, o))
+          } else {
+            Err(Err::Error(nom_ws_error_position!(i_, ErrorKind::Verify)))
+          }
+        }
+      }
+    }
+  );
+  ($i:expr, $separator:path, $f:expr, $g:expr) => (
+    verify_input_sep!($i, $separator, nom_call!($f), $g)
+  );
+);
+
+#[doc(hidden)]
+#[macro_export(local_inner_macros)]
+macro_rules! offset_of_sep (
+  ($i:expr, $separator:path, $original:expr, $submac:ident!( $($args:tt)* )) => (
+    {
+      use $crate::lib::std::result::Result::*;
+      use $crate::lib::nom::Offset;
+
+      let i_ = $i.clone();
+      let offset = $original.offset(&i_);
+      match sep!(i_, $separator, $submac!($($args)*)) {
+        Err(e)    => Err(e),
+        Ok((i,o)) => Ok((i, (o, offset)))
+      }
+    }
+  );
+  ($i:expr, $separator:path, $original:expr, $f:expr) => (
+    offset_of_sep!($i, $separator, $original, nom_call!($f))
+  );
+);
+
+/// like `peek!`, but returns the length the child parser would have
+/// consumed, including the separator before it
+#[doc(hidden)]
+#[macro_export(local_inner_macros)]
+macro_rules! peek_len_sep (
+  ($i:expr, $separator:path, $submac:ident!( $($args:tt)* )) => (
+    {
+      use $crate::lib::std::result::Result::*;
+      use $crate::lib::nom::InputLength;
+
+      let i_ = $i.clone();
+      match sep!(i_.clone(), $separator, $submac!($($args)*)) {
+        Err(e)    => Err(e),
+        Ok((i,_)) => {
+          let len = i_.input_len() - i.input_len();
+          Ok((i_, len))
+        }
+      }
+    }
+  );
+  ($i:expr, $separator:path, $f:expr) => (
+    peek_len_sep!($i, $separator, nom_call!($f))
+  );
+);
+
+/// the separator is not consumed: on success, the input is returned as is
+#[doc(hidden)]
+#[macro_export(local_inner_macros)]
+macro_rules! not_sep (
+  ($i:expr, $separator:path, $submac:ident!( $($args:tt)* )) => (
+    {
+      use $crate::lib::std::result::Result::*;
+      use $crate::lib::nom::{Convert,Err,ErrorKind};
+
+      let i_ = $i.clone();
+      match ($separator)(i_.clone()) {
+        Err(e)     => Err(Err::convert(e)),
+        Ok((i1,_)) => {
+          match sep!(i1.clone(), $separator, $submac!($($args)*)) {
+            Err(Err::Failure(e))    => Err(Err::Failure(e)),
+            Err(Err::Incomplete(n)) => Err(Err::Incomplete(n)),
+            Err(_)                  => Ok((i_, ())),
+            Ok(_)                   => Err(Err::Error(nom_ws_error_position!(i1, ErrorKind::Not))),
+          }
+        }
+      }
+    }
+  );
+  ($i:expr, $separator:path, $f:expr) => (
+    not_sep!($i, $separator, nom_call!($f))
+  );
+);
+
+/// the separator is only consumed if the child parser consumed something
+/// after it. Otherwise (no match, or a zero width match), the input is
+/// returned as is
+#[doc(hidden)]
+#[macro_export(local_inner_macros)]
+macro_rules! opt_sep (
+  ($i:expr, $separator:path, $submac:ident!( $($args:tt)* )) => (
+    {
+      use $crate::lib::std::result::Result::*;
+      use $crate::lib::std::option::Option::*;
+      use $crate::lib::nom::{Err,Convert,InputLength};
+
+      let i_ = $i.clone();
+      match ($separator)(i_.clone()) {
+        Err(Err::Error(_)) => Ok((i_, None)),
+        Err(e)             => Err(Err::convert(e)),
+        Ok((i1,_))         => {
+          let len = i1.input_len();
+          match sep!(i1, $separator, $submac!($($args)*)) {
+            Ok((i2,o))         => {
+              if i2.input_len() == len {
+                Ok((i_, Some(o)))
+              } else {
+                Ok((i2, Some(o)))
+              }
+            },
+            Err(Err::Error(_)) => Ok((i_, None)),
+            Err(e)             => Err(e),
+          }
+        }
+      }
+    }
+  );
+  ($i:expr, $separator:path, $f:expr) => (
+    opt_sep!($i, $separator, nom_call!($f))
+  );
+);
+
+/// like `opt_sep!`, but only returns whether the child parser matched
+#[doc(hidden)]
+#[macro_export(local_inner_macros)]
+macro_rules! opt_flag_sep (
+  ($i:expr, $separator:path, $($rest:tt)*) => (
+    {
+      use $crate::lib::std::result::Result::*;
+
+      match opt_sep!($i, $separator, $($rest)*) {
+        Err(e)    => Err(e),
+        Ok((i,o)) => Ok((i, o.is_some()))
+      }
+    }
+  );
+);
+
+/// helper macros to build a separator parser
+///
+/// ```
+/// # #[macro_use] extern crate nom;
+/// # #[macro_use] extern crate nom_whitespace;
+/// named!(pub space, eat_separator!(&b" \t"[..]));
+/// # fn main() {}
+/// ```
+#[macro_export(local_inner_macros)]
+macro_rules! eat_separator (
+  ($i:expr, $arr:expr) => (
+    {
+      use $crate::lib::nom::{FindToken, InputTakeAtPosition};
+      let input = $i;
+      input.split_at_position(|c| !$arr.find_token(c))
+    }
+  );
+);
+
+/// builds a separator parser from other separators
+///
+/// each separator is tried in turn, until none of them
+/// consumes anything. Their results are discarded, so they can be
+/// of any type. It returns the consumed input
+///
+/// ```
+/// # #[macro_use] extern crate nom;
+/// # #[macro_use] extern crate nom_whitespace;
+/// # use nom::types::CompleteStr;
+/// # use nom_whitespace::sp;
+/// named!(comment<CompleteStr, CompleteStr>, preceded!(tag!("#"), take_while!(|c| c != '\n')));
+/// named!(space<CompleteStr, CompleteStr>, alt_sep_fn!(sp, comment));
+///
+/// # fn main() {
+/// assert_eq!(space(CompleteStr(" # a\n  # b\n c")), Ok((CompleteStr("c"), CompleteStr(" # a\n  # b\n "))));
+/// # }
+/// ```
+#[macro_export(local_inner_macros)]
+macro_rules! alt_sep_fn (
+  ($i:expr, $($separator:expr),+) => (
+    {
+      use $crate::lib::std::result::Result::*;
+      use $crate::lib::std::option::Option::*;
+      use $crate::lib::nom::{Err,InputLength,InputTake,Offset};
+
+      let i_ = $i.clone();
+      let mut input = i_.clone();
+      let mut error = None;
+      loop {
+        let len = input.input_len();
+        $(
+          if error.is_none() {
+            match ($separator)(input.clone()) {
+              Ok((i,_))          => input = i,
+              Err(Err::Error(_)) => {},
+              Err(e)             => error = Some(e),
+            }
+          }
+        )+
+        if error.is_some() || input.input_len() == len {
+          break;
+        }
+      }
+
+      match error {
+        Some(e) => Err(e),
+        None    => {
+          let consumed = i_.offset(&input);
+          Ok((input, i_.take(consumed)))
+        }
+      }
+    }
+  );
+);
+
+/// builds a separator skipping any number of "ignorable" parsers
+///
+/// this is the same as `alt_sep_fn!`, under a name that reads better
+/// when the parsers are not whitespace, like directives or comments
+/// parsed into a value
+///
+/// ```
+/// # #[macro_use] extern crate nom;
+/// # #[macro_use] extern crate nom_whitespace;
+/// # use nom::alpha;
+/// # use nom::types::CompleteStr;
+/// # use nom_whitespace::sp;
+/// named!(pragma<CompleteStr, (CompleteStr, CompleteStr)>, pair!(tag!("@"), alpha));
+/// named!(ignored<CompleteStr, CompleteStr>, sep_ignoring!(sp, pragma));
+///
+/// # fn main() {
+/// assert_eq!(ignored(CompleteStr(" @inline a")), Ok((CompleteStr("a"), CompleteStr(" @inline "))));
+/// # }
+/// ```
+#[macro_export(local_inner_macros)]
+macro_rules! sep_ignoring (
+  ($i:expr, $($ignorable:expr),+) => (
+    alt_sep_fn!($i, $($ignorable),+)
+  );
+);
+
+/// recognizes a tag, if it is followed by a word boundary
+///
+/// the tag does not match if the next character is a word character,
+/// as defined by the optional predicate. By default, alphanumeric
+/// characters and `_` are word characters. The tag can be preceded
+/// by anything, so in `ws!`, it matches after whitespace, but also
+/// right after punctuation consumed by the previous parser
+///
+/// ```
+/// # #[macro_use] extern crate nom;
+/// # #[macro_use] extern crate nom_whitespace;
+/// # use nom::types::CompleteStr;
+/// named!(cond<CompleteStr, CompleteStr>,
+///   ws!(delimited!(char!('('), tag_bounded!("if"), char!(')')))
+/// );
+///
+/// # fn main() {
+/// assert_eq!(cond(CompleteStr(" (if )")), Ok((CompleteStr(""), CompleteStr("if"))));
+/// assert!(cond(CompleteStr("(iffy)")).is_err());
+/// # }
+/// ```
+#[macro_export(local_inner_macros)]
+macro_rules! tag_bounded (
+  ($i:expr, $tag:expr) => (
+    tag_bounded!($i, $tag, |c: char| c.is_alphanumeric() || c == '_')
+  );
+  ($i:expr, $tag:expr, $word_char:expr) => (
+    {
+      use $crate::lib::std::result::Result::*;
+      use $crate::lib::std::option::Option::*;
+      use $crate::lib::nom::{AsChar,AtEof,Err,ErrorKind,InputIter,Needed};
+
+      let i_ = $i.clone();
+      match nom_tag!(i_.clone(), $tag) {
+        Err(e)    => Err(e),
+        Ok((i,o)) => match i.iter_elements().next() {
+          None if !i.at_eof()                 => Err(Err::Incomplete(Needed::Unknown)),
+          Some(c) if ($word_char)(c.as_char()) => Err(Err::Error(nom_ws_error_position!(i_, ErrorKind::Tag))),
+          _                                   => Ok((i, o)),
+        }
+      }
+    }
+  );
+);
+
+/// like `tag_bounded!`, but matches the keyword case insensitively,
+/// with `tag_no_case!`, and returns the input as written
+///
+/// ```
+/// # #[macro_use] extern crate nom;
+/// # #[macro_use] extern crate nom_whitespace;
+/// # use nom::alpha;
+/// # use nom::types::CompleteStr;
+/// named!(select<CompleteStr, CompleteStr>,
+///   ws!(preceded!(keyword_ci!("SELECT"), alpha))
+/// );
+///
+/// # fn main() {
+/// assert_eq!(select(CompleteStr(" Select name")), Ok((CompleteStr(""), CompleteStr("name"))));
+/// assert!(select(CompleteStr("selected name")).is_err());
+/// # }
+/// ```
+#[macro_export(local_inner_macros)]
+macro_rules! keyword_ci (
+  ($i:expr, $tag:expr) => (
+    keyword_ci!($i, $tag, |c: char| c.is_alphanumeric() || c == '_')
+  );
+  ($i:expr, $tag:expr, $word_char:expr) => (
+    {
+      use $crate::lib::std::result::Result::*;
+      use $crate::lib::std::option::Option::*;
+      use $crate::lib::nom::{AsChar,AtEof,Err,ErrorKind,InputIter,Needed};
+
+      let i_ = $i.clone();
+      match nom_tag_no_case!(i_.clone(), $tag) {
+        Err(e)    => Err(e),
+        Ok((i,o)) => match i.iter_elements().next() {
+          None if !i.at_eof()                 => Err(Err::Incomplete(Needed::Unknown)),
+          Some(c) if ($word_char)(c.as_char()) => Err(Err::Error(nom_ws_error_position!(i_, ErrorKind::Tag))),
+          _                                   => Ok((i, o)),
+        }
+      }
+    }
+  );
+);
+
+/// applies a separator, like one built with `alt_sep_fn!` to skip
+/// whitespace and comments, then `tag_bounded!`
+///
+/// as with `tag_bounded!`, the word characters can be given with
+/// an optional predicate
+///
+/// ```
+/// # #[macro_use] extern crate nom;
+/// # #[macro_use] extern crate nom_whitespace;
+/// # use nom::types::CompleteStr;
+/// # use nom_whitespace::sp;
+/// named!(comment<CompleteStr, CompleteStr>, preceded!(tag!("#"), take_while!(|c| c != '\n')));
+/// named!(space<CompleteStr, CompleteStr>, alt_sep_fn!(sp, comment));
+/// named!(end<CompleteStr, CompleteStr>, tag_skip_comments!("end", space));
+///
+/// # fn main() {
+/// assert_eq!(end(CompleteStr(" # done\n end")), Ok((CompleteStr(""), CompleteStr("end"))));
+/// assert!(end(CompleteStr(" # done\n ending")).is_err());
+/// # }
+/// ```
+#[macro_export(local_inner_macros)]
+macro_rules! tag_skip_comments (
+  ($i:expr, $tag:expr, $separator:expr) => (
+    tag_skip_comments!($i, $tag, $separator, |c: char| c.is_alphanumeric() || c == '_')
+  );
+  ($i:expr, $tag:expr, $separator:expr, $word_char:expr) => (
+    {
+      use $crate::lib::std::result::Result::*;
+      use $crate::lib::nom::{Convert,Err};
+
+      match ($separator)($i.clone()) {
+        Err(e)     => Err(Err::convert(e)),
+        Ok((i1,_)) => tag_bounded!(i1, $tag, $word_char),
+      }
+    }
+  );
+);
+
+/// recognizes a blank line boundary
+///
+/// matches a run of whitespace containing at least two newlines,
+/// like a paragraph break, and returns the whole run. Inside `sep!`
+/// and `ws!`, it is applied before the separator, so it can
+/// distinguish a paragraph break from a single line break
+///
+/// ```
+/// # #[macro_use] extern crate nom;
+/// # #[macro_use] extern crate nom_whitespace;
+/// # use nom::alpha;
+/// # use nom::types::CompleteStr;
+/// named!(paragraphs<CompleteStr, (CompleteStr, CompleteStr)>,
+///   ws!(separated_pair!(alpha, paragraph_break!(), alpha))
+/// );
+///
+/// # fn main() {
+/// assert_eq!(
+///   paragraphs(CompleteStr("a\n  \n b")),
+///   Ok((CompleteStr(""), (CompleteStr("a"), CompleteStr("b"))))
+/// );
+/// assert!(paragraphs(CompleteStr("a\n b")).is_err());
+/// # }
+/// ```
+#[macro_export(local_inner_macros)]
+macro_rules! paragraph_break (
+  ($i:expr,) => (
+    paragraph_break!($i)
+  );
+  ($i:expr) => (
+    {
+      use $crate::lib::std::result::Result::*;
+      use $crate::lib::std::option::Option::*;
+      use $crate::lib::nom::{AsChar,AtEof,Err,ErrorKind,InputIter,InputLength,InputTake,Needed};
+
+      let i_ = $i.clone();
+      let mut newlines = 0usize;
+      let mut end = None;
+      for (idx, item) in i_.iter_indices() {
+        match item.as_char() {
+          '\n'             => newlines += 1,
+          ' ' | '\t' | '\r' => {},
+          _                => {
+            end = Some(idx);
+            break;
+          },
+        }
+      }
+
+      match end {
+        None if !i_.at_eof() => Err(Err::Incomplete(Needed::Size(1))),
+        _                    => {
+          if newlines < 2 {
+            Err(Err::Error(nom_ws_error_position!(i_, ErrorKind::MultiSpace)))
+          } else {
+            let idx = end.unwrap_or(i_.input_len());
+            Ok(i_.take_split(idx))
+          }
+        }
+      }
+    }
+  );
+);
+
+/// wraps a separator parser to limit how much input it consumes
+///
+/// the separator is only applied on the first `max + 1` elements of
+/// the input, so a longer run is never scanned, and if it consumes more
+/// than `max` elements, it fails with `ErrorKind::Custom(SEP_BOUNDED_ERROR)`
+/// at the start of the separator. That error is an `Err::Failure`, so
+/// enclosing combinators will not backtrack and rescan the same input.
+/// It returns the consumed input
+///
+/// ```
+/// # #[macro_use] extern crate nom;
+/// # #[macro_use] extern crate nom_whitespace;
+/// # use nom::{Err, ErrorKind};
+/// # use nom::types::CompleteStr;
+/// # use nom_whitespace::{sp, SEP_BOUNDED_ERROR};
+/// named!(space<CompleteStr, CompleteStr>, sep_bounded!(4, sp));
+/// named!(pair<CompleteStr, (CompleteStr, CompleteStr)>,
+///   sep!(space, tuple!(tag!("a"), tag!("b")))
+/// );
+/// # fn main() {
+/// assert_eq!(pair(CompleteStr(" a b")), Ok((CompleteStr(""), (CompleteStr("a"), CompleteStr("b")))));
+/// assert_eq!(
+///   pair(CompleteStr("a      b")),
+///   Err(Err::Failure(error_position!(CompleteStr("      b"), ErrorKind::Custom(SEP_BOUNDED_ERROR))))
+/// );
+/// # }
+/// ```
+#[macro_export(local_inner_macros)]
+macro_rules! sep_bounded (
+  ($i:expr, $max:expr, $separator:path) => (
+    {
+      use $crate::lib::std::result::Result::*;
+      use $crate::lib::nom::{Err,ErrorKind,InputLength,InputTake};
+
+      let i_ = $i.clone();
+      let len = i_.input_len();
+      let max: usize = $max;
+      let bounded = if len > max { i_.take(max + 1) } else { i_.clone() };
+      let bounded_len = bounded.input_len();
+
+      match $separator(bounded) {
+        Ok((i, _)) => {
+          let consumed = bounded_len - i.input_len();
+          if consumed > max {
+            Err(Err::Failure(nom_ws_error_position!(i_, ErrorKind::Custom($crate::SEP_BOUNDED_ERROR))))
+          } else {
+            Ok(i_.take_split(consumed))
+          }
+        },
+        // a partial input separator reached the end of the bound
+        Err(Err::Incomplete(_)) if len > max => {
+          Err(Err::Failure(nom_ws_error_position!(i_, ErrorKind::Custom($crate::SEP_BOUNDED_ERROR))))
+        },
+        Err(e) => Err(e),
+      }
+    }
+  );
+);
+
+/// wraps a separator parser to trace its calls
+///
+/// the callback receives the input and the result of each
+/// call to the separator. Without a callback, calls are
+/// printed on stderr (this requires the `debug` feature)
+///
+/// ```
+/// # #[macro_use] extern crate nom;
+/// # #[macro_use] extern crate nom_whitespace;
+/// # use nom::IResult;
+/// # use nom::types::CompleteStr;
+/// # use nom_whitespace::sp;
+/// fn log(input: &CompleteStr, res: &IResult<CompleteStr, CompleteStr>) {
+///   println!("separator at {:?}: {:?}", input, res);
+/// }
+///
+/// named!(traced<CompleteStr, CompleteStr>, trace_sep!(sp, log));
+/// named!(tuple<CompleteStr, (CompleteStr, CompleteStr)>,
+///   sep!(traced, tuple!(tag!("a"), tag!("b")))
+/// );
+/// # fn main() {
+/// # assert_eq!(tuple(CompleteStr(" a b")), Ok((CompleteStr(""), (CompleteStr("a"), CompleteStr("b")))));
+/// # }
+/// ```
+#[macro_export(local_inner_macros)]
+macro_rules! trace_sep (
+  ($i:expr, $separator:path, $callback:expr) => (
+    {
+      let i_ = $i.clone();
+      let res = ($separator)(i_.clone());
+      ($callback)(&i_, &res);
+      res
+    }
+  );
+  ($i:expr, $separator:path) => (
+    trace_sep!($i, $separator, $crate::trace_print)
+  );
+);
+
+/// sep is the parser rewriting macro for whitespace separated formats
+///
+/// it takes as argument a space eating function and a parser tree,
+/// and will intersperse the space parser everywhere
+///
+/// In addition to the nom combinators, it recognizes the following ones,
+/// that are only available inside `sep!` and `ws!`:
+///
+/// * `keyword_set!(p1, p2, ...)`: applies each parser at most once, in any
+///   order, and returns a `[bool; N]` indicating which ones matched
+/// * `opt_prefixes!([p1, p2, ...], p)`: applies each of the prefix parsers
+///   in order, each being optional, then `p`, and returns `([bool; N], O)`,
+///   indicating which prefixes were present, and the result of `p`
+/// * `terminated_opt!(p, terminator)`: like `terminated!`, but the terminator
+///   can be omitted at the end of input
+/// * `peek_len!(p)`: like `peek!`, but returns the length `p` would have
+///   consumed, including the separator before it
+/// * `opt_flag!(p)`: like `opt!`, but returns `true` if `p` matched, and
+///   `false` otherwise
+/// * `ws_tok!(separator, p)`: applies `p` with another separator
+/// * `many0_acc!(p, init, push)`: same as `fold_many0!`, named to signal
+///   that the results are accumulated in an arbitrary container, `init`
+/// * `fold_many0_while!(p, init, fold)`: like `fold_many0!`, but `fold`
+///   returns a `ControlFlow`. On `ControlFlow::Break(acc)`, it stops after
+///   the current element and returns `acc`, leaving the rest of the input
+/// * `fold_many1!(p, init, fold)`: same as `fold_many1!`, but if an element
+///   after the first one fails with `Err::Failure`, the error kind is
+///   `ErrorKind::Custom(index)`, the index of the element in the list.
+///   The error type must implement `From<u32>`
+/// * `list_until!(p, end)`: applies `p` until `end` matches, and returns the
+///   `Vec` of results, without the result of `end`, which is consumed. It
+///   fails with `ErrorKind::ManyTill` if `p` fails before `end`
+/// * `many_till_peek!(p, end)`: like `many_till!`, applies `p` until `end`
+///   matches, but returns only the `Vec` of results, and leaves `end` in the
+///   input, along with the separator before it, for the parent parser to
+///   consume. It fails with `ErrorKind::ManyTill` if `p` fails before `end`
+/// * `many_same!(p)`: like `many1!`, but all the results must be equal,
+///   and it returns `(count, value)`. It fails with
+///   `ErrorKind::Custom(MANY_SAME_ERROR)` on the first different element
+/// * `take_while_m_n!(m, n, pred)`: the run starts after the separator, then
+///   nom's `take_while_m_n!` applies, so on complete input, a run reaching the
+///   end of the input is accepted if it is at least `m` long
+/// * `count_range!(m, n, p)`: same as `many_m_n!`, applies `p` between `m`
+///   and `n` times and returns the results in a `Vec`
+/// * `success!(value)`: always succeeds with `value`, without consuming the
+///   separator. In `alt!`, the separator before the branches is already
+///   consumed, `alt_fallback!` keeps it
+/// * `alt_fallback!(p1 | p2)`: like `alt!`, but each branch consumes the
+///   separator that precedes it instead of `alt!` consuming it once
+///   before them, so a `success!` fallback leaves the input as is
+/// * `tuple!(p1, p2?, p3)`: an element followed by `?` is optional, and
+///   returns an `Option`
+/// * `separated_pair_alt!(a, [m1, m2, ...], b)`: like `separated_pair!`, but
+///   the middle can be any of the listed parsers, which must be macros (like
+///   `tag!("=")` or `call!(f)`). They are tried in order, as with `alt!`, so a
+///   parser must be listed before its prefixes. It returns `(O1, M, O2)`, with
+///   the result of the middle parser that matched
+/// * `pair_ws_count!(a, b)`: like `pair!`, but returns `(O1, O2, usize)`,
+///   the last element being the length of the separator between `a` and `b`
+/// * `binop_level!(next_level, operator, fold)`: parses a left associative
+///   binary operator level, like `1 + 2 - 3`, with `next_level` parsing the
+///   operands. The results are combined with `fold(acc, operator, operand)`
+/// * `flat_map_ws!(p, q)`: like `flat_map!`, but the separator is also
+///   used when `q` parses the output of `p`, while `flat_map!` applies `q`
+///   to it as is
+/// * `head_then_sep!(first, rest)`: applies `first` without skipping
+///   the separator before it, then `rest` with the separator, and
+///   returns both results as a tuple
+/// * `length_data!(count)`: the separator is consumed before and after
+///   the count, but the data itself is taken verbatim, without any
+///   separator processing
+/// * `length_data_no_sep!(count)`: like `length_data!`, but the data is
+///   taken right after the count, so whitespace after it is part of the data
+/// * `paragraph_break!()`: applied before the separator, see `paragraph_break!`
+/// * `columns!(n)`: splits a line in exactly `n` columns, separated by the
+///   separator, the last column extending to the end of the line, and
+///   returns them in a `Vec`. It fails on an end of line found before
+///   the last column
+/// * `indented_block!(parent_indent, line)`: starting at the beginning of
+///   a line, parses consecutive lines indented by more than `parent_indent`
+///   spaces or tabs with `line`, each followed by the end of the line, and
+///   returns them in a `Vec`. It stops on a dedent, and skips blank lines
+///   inside the block, as in YAML or Python. Since
+///   the line parser should not consume line endings, it is meant to be
+///   used with `inline_sp` as separator
+/// * `bracketed_list!(open, separator, p, close)`: like
+///   `delimited!(open, separated_list!(separator, p), close)`, returns the
+///   elements in a `Vec`, which is empty for `[]` or `[ ]`
+/// * `flags_and_kv!(key, kv_separator, value)`: a list of bare flags and
+///   key-value pairs, like `--verbose --level=3`, separated by the separator,
+///   returned as a `Vec<(K, Option<V>)>`
+/// * `balanced!(open, close, p)`: parses a group from `open` to `close`,
+///   containing elements recognized by `p` and nested groups, to any depth,
+///   and returns it as a `Balanced` tree. An unclosed group fails with the
+///   error of `close`
+/// * `kv_list!(separator, kv_separator, key, value)`: a list of key-value pairs,
+///   returned as a `Vec<(K, V)>`
+/// * `map_result!(p, f)`: like `map_res!`, applies `f`, returning a `Result`,
+///   on the result of `p`, and fails with `ErrorKind::MapRes` on `Err`
+/// * `escaped_flagged!(normal, control_char, escapable)`: like `escaped!`, but
+///   returns `(I, bool)`, the raw input and whether it contained any escape
+/// * `cond_with_error!(cond, p)`: if `cond` is false, returns `None` without
+///   consuming the separator
+/// * `cond_reduce!(cond, code, p)`: like `cond_reduce!(cond, p)`, but fails
+///   with `ErrorKind::Custom(code)` if `cond` is false
+/// * `map_reject!(p, f, is_reject)`: like `map!`, but fails with
+///   `ErrorKind::MapOpt` if `is_reject` returns true for the mapped value,
+///   like a sentinel returned by `f`
+/// * `verify_input!(p, f)`: like `verify!`, but `f` receives both the result
+///   of `p` and the input it consumed (without the leading separator), and
+///   fails with `ErrorKind::Verify` if it returns false
+/// * `separated_set!(separator, p)`: like `separated_list!`, but collects the
+///   elements in a `HashSet`, silently ignoring duplicates (requires the `std` feature)
+/// * `recognize_trim!(p)`: like `recognize!`, but what the separator would
+///   consume at the end of the consumed input, like spaces, line endings or
+///   comments consumed by a `take_while!`, is not included in the result
+/// * `number_list!(p)`: parses the numbers recognized by `p`, separated by the
+///   separator, and converts them with `nom::ParseTo` into a `Vec`, like a
+///   `Vec<u32>`, so `p` must return a string or byte slice type. The list
+///   stops before the first token that `p` does not recognize, or that cannot
+///   be converted, so it can be followed by other parsers
+/// * `separated_list_flex!(p)`: a list where the elements are separated by
+///   a comma, a run of the separator, or both, like `1 2, 3 ,4`
+/// * `separated_list_budget!(max, separator, p)`: like `separated_list!`, but
+///   fails with `ErrorKind::Custom(LIST_BUDGET_ERROR)` as soon as more than
+///   `max` elements are parsed, to limit the memory used on untrusted input
+/// * `separated_list_spanned!(original, separator, p)`: like `separated_list!`,
+///   but returns each element with its offset, from the start of `original`,
+///   as `Vec<(usize, O)>`. As with `offset_of!`, `original` must be the
+///   complete input, and implement `nom::Offset`
+/// * `no_ws!(p)`: applies `p` as is, without any separator, like a
+///   fixed width field in a `do_parse!` chain
+/// * `without_ws!(p)`: applies the separator, then `p` with `no_sep` as separator,
+///   so no whitespace is skipped in `p` and its children. Unlike `no_ws!`, the
+///   combinators specific to `sep!` can still be used in `p`
+/// * `separator_errors!(p)`: applies `p` with a separator whose errors are
+///   tagged with `ErrorKind::Custom(SEPARATOR_ERROR)`, to tell them apart
+///   from the errors of the parsers. The original error is kept as a child
+///   with nom's `verbose-errors`, and errors that already have a custom code
+///   are left as is
+/// * `offset_of!(original, p)`: returns the result of `p` along with
+///   the offset, from the start of `original`, at which `p` started,
+///   after the separator, as `(O, usize)`. `original` must be the
+///   complete input, and implement `nom::Offset`
+/// * `alt_into!(p1 | p2 | ...)`: like `alt!`, but converts the result of each
+///   branch with `Into::into`, so branches can have different output types
+/// * `alt_tagged!(p1 | p2 | ...)`: like `alt!`, but returns the index of the
+///   branch that matched, starting from 0, with its result, as `(usize, O)`
+/// * `alt_ambiguous!(p1 | p2 | ...)`: like `alt!`, but tries all the branches,
+///   and returns the result of the first one that matched with the number of
+///   branches that matched, as `(O, usize)`. A count larger than 1 indicates an
+///   ambiguity in the grammar
+/// * `switch!(discriminant, a => pa | b => pb | _ => default)`: as with `switch!`,
+///   a last `_` arm is a fallthrough, which applies `default`, with the separator,
+///   to the input following the discriminant when no other arm matched
+/// * `separated_list_keep_sep!(separator, p)`: like `separated_list!`, but
+///   returns the separators too, as `(Vec<O>, Vec<S>)`
+///
+/// ```ignore
+/// #[macro_export(local_inner_macros)]
+/// macro_rules! ws (
+///   ($i:expr, $($args:tt)*) => (
+///     {
+///       use sp;
+///       sep!($i, sp, $($args)*)
+///     }
+///   )
+/// );
+/// ```
+#[macro_export(local_inner_macros)]
+macro_rules! sep (
+  ($i:expr,  $separator:path, tuple ! ($($rest:tt)*) ) => {
+    tuple_sep!($i, $separator, (), $($rest)*)
+  };
+  ($i:expr,  $separator:path, pair ! ($($rest:tt)*) ) => {
+    wrap_sep!($i,
+      $separator,
+      pair_sep!($separator, $($rest)*)
+    )
+  };
+  ($i:expr,  $separator:path, delimited ! ($($rest:tt)*) ) => {
+    wrap_sep!($i,
+      $separator,
+      delimited_sep!($separator, $($rest)*)
+    )
+  };
+  ($i:expr,  $separator:path, separated_pair ! ($($rest:tt)*) ) => {
+    wrap_sep!($i,
+      $separator,
+      separated_pair_sep!($separator, $($rest)*)
+    )
+  };
+  ($i:expr,  $separator:path, separated_pair_alt ! ($($rest:tt)*) ) => {
+    separated_pair_alt_sep!($i, $separator, $($rest)*)
+  };
+  ($i:expr,  $separator:path, preceded ! ($($rest:tt)*) ) => {
+    wrap_sep!($i,
+      $separator,
+      preceded_sep!($separator, $($rest)*)
+    )
+  };
+  ($i:expr,  $separator:path, terminated ! ($($rest:tt)*) ) => {
+    wrap_sep!($i,
+      $separator,
+      terminated_sep!($separator, $($rest)*)
+    )
+  };
+  ($i:expr,  $separator:path, terminated_opt ! ($($rest:tt)*) ) => {
+    wrap_sep!($i,
+      $separator,
+      terminated_opt_sep!($separator, $($rest)*)
+    )
+  };
+  ($i:expr,  $separator:path, do_parse ! ($($rest:tt)*) ) => {
+    wrap_sep!($i,
+      $separator,
+      do_parse_sep!($separator, $($rest)*)
+    )
+  };
+  ($i:expr,  $separator:path, permutation ! ($($rest:tt)*) ) => {
+    wrap_sep!($i,
+      $separator,
+      permutation_sep!($separator, $($rest)*)
+    )
+  };
+  ($i:expr,  $separator:path, keyword_set ! ($($rest:tt)*) ) => {
+    wrap_sep!($i,
+      $separator,
+      keyword_set_sep!($separator, $($rest)*)
+    )
+  };
+  ($i:expr,  $separator:path, opt_prefixes ! ($($rest:tt)*) ) => {
+    opt_prefixes_sep!($i, $separator, $($rest)*)
+  };
+  ($i:expr,  $separator:path, alt_into ! ($($rest:tt)*) ) => {
+    alt_into_sep!($i, $separator, $($rest)*)
+  };
+  ($i:expr,  $separator:path, alt_tagged ! ($($rest:tt)*) ) => {
+    alt_tagged_sep!($i, $separator, $($rest)*)
+  };
+  ($i:expr,  $separator:path, alt_ambiguous ! ($($rest:tt)*) ) => {
+    alt_ambiguous_sep!($i, $separator, $($rest)*)
+  };
+  ($i:expr,  $separator:path, alt ! ($($rest:tt)*) ) => {
+    wrap_sep!($i,
+      $separator,
+      alt_sep!($separator, $($rest)*)
+    )
+  };
+  ($i:expr,  $separator:path, alt_fallback ! ($($rest:tt)*) ) => {
+    {
+      let i_ = $i.clone();
+      alt_sep!(i_, $separator, $($rest)*)
+    }
+  };
+  ($i:expr,  $separator:path, alt_complete ! ($($rest:tt)*) ) => {
+    wrap_sep!($i,
+      $separator,
+      alt_complete_sep!($separator, $($rest)*)
+    )
+  };
+  ($i:expr,  $separator:path, switch ! ($($rest:tt)*) ) => {
+    wrap_sep!($i,
+      $separator,
+      switch_sep!($separator, $($rest)*)
+    )
+  };
+  ($i:expr,  $separator:path, separated_list ! ($($rest:tt)*) ) => {
+    wrap_sep!($i,
+      $separator,
+      separated_list_sep!($separator, $($rest)*)
+    )
+  };
+  ($i:expr,  $separator:path, separated_list_spanned ! ($($rest:tt)*) ) => {
+    separated_list_spanned_sep!($i, $separator, $($rest)*)
+  };
+  ($i:expr,  $separator:path, separated_list_flex ! ($($rest:tt)*) ) => {
+    wrap_sep!($i,
+      $separator,
+      separated_list_flex_sep!($separator, $($rest)*)
+    )
+  };
+  ($i:expr,  $separator:path, separated_list_budget ! ($($rest:tt)*) ) => {
+    wrap_sep!($i,
+      $separator,
+      separated_list_budget_sep!($separator, $($rest)*)
+    )
+  };
+  ($i:expr,  $separator:path, separated_set ! ($($rest:tt)*) ) => {
+    wrap_sep!($i,
+      $separator,
+      separated_set_sep!($separator, $($rest)*)
+    )
+  };
+  ($i:expr,  $separator:path, separated_list_keep_sep ! ($($rest:tt)*) ) => {
+    wrap_sep!($i,
+      $separator,
+      separated_list_keep_sep_sep!($separator, $($rest)*)
+    )
+  };
+  ($i:expr,  $separator:path, pair_ws_count ! ($($rest:tt)*) ) => {
+    pair_ws_count_sep!($i, $separator, $($rest)*)
+  };
+  ($i:expr,  $separator:path, binop_level ! ($($rest:tt)*) ) => {
+    binop_level_sep!($i, $separator, $($rest)*)
+  };
+  ($i:expr,  $separator:path, flat_map_ws ! ($($rest:tt)*) ) => {
+    flat_map_ws_sep!($i, $separator, $($rest)*)
+  };
+  ($i:expr,  $separator:path, head_then_sep ! ($($rest:tt)*) ) => {
+    head_then_sep_sep!($i, $separator, $($rest)*)
+  };
+  ($i:expr,  $separator:path, paragraph_break ! ($($rest:tt)*) ) => {
+    paragraph_break!($i, $($rest)*)
+  };
+  ($i:expr,  $separator:path, indented_block ! ($($rest:tt)*) ) => {
+    indented_block_sep!($i, $separator, $($rest)*)
+  };
+  ($i:expr,  $separator:path, columns ! ($($rest:tt)*) ) => {
+    wrap_sep!($i,
+      $separator,
+      columns_sep!($separator, $($rest)*)
+    )
+  };
+  ($i:expr,  $separator:path, list_until ! ($($rest:tt)*) ) => {
+    list_until_sep!($i, $separator, $($rest)*)
+  };
+  ($i:expr,  $separator:path, many_till_peek ! ($($rest:tt)*) ) => {
+    many_till_peek_sep!($i, $separator, $($rest)*)
+  };
+  ($i:expr,  $separator:path, flags_and_kv ! ($($rest:tt)*) ) => {
+    wrap_sep!($i,
+      $separator,
+      flags_and_kv_sep!($separator, $($rest)*)
+    )
+  };
+  ($i:expr,  $separator:path, balanced ! ($($rest:tt)*) ) => {
+    balanced_sep!($i, $separator, $($rest)*)
+  };
+  ($i:expr,  $separator:path, bracketed_list ! ($($rest:tt)*) ) => {
+    bracketed_list_sep!($i, $separator, $($rest)*)
+  };
+  ($i:expr,  $separator:path, kv_list ! ($($rest:tt)*) ) => {
+    wrap_sep!($i,
+      $separator,
+      kv_list_sep!($separator, $($rest)*)
+    )
+  };
+  ($i:expr,  $separator:path, length_data ! ($($rest:tt)*) ) => {
+    length_data_sep!($i, $separator, $($rest)*)
+  };
+  ($i:expr,  $separator:path, length_data_no_sep ! ($($rest:tt)*) ) => {
+    length_data_no_sep_sep!($i, $separator, $($rest)*)
+  };
+  ($i:expr,  $separator:path, value ! ($($rest:tt)*) ) => {
+    wrap_sep!($i,
+      $separator,
+      value_sep!($separator, $($rest)*)
+    )
+  };
+  ($i:expr,  $separator:path, map ! ($($rest:tt)*) ) => {
+    wrap_sep!($i,
+      $separator,
+      map_sep!($separator, $($rest)*)
+    )
+  };
+  ($i:expr,  $separator:path, map_reject ! ($($rest:tt)*) ) => {
+    wrap_sep!($i,
+      $separator,
+      map_reject_sep!($separator, $($rest)*)
+    )
+  };
+  ($i:expr,  $separator:path, verify ! ($($rest:tt)*) ) => {
+    wrap_sep!($i,
+      $separator,
+      verify_sep!($separator, $($rest)*)
+    )
+  };
+  ($i:expr,  $separator:path, map_result ! ($($rest:tt)*) ) => {
+    wrap_sep!($i,
+      $separator,
+      map_result_sep!($separator, $($rest)*)
+    )
+  };
+  ($i:expr,  $separator:path, escaped_flagged ! ($($rest:tt)*) ) => {
+    wrap_sep!($i,
+      $separator,
+      escaped_flagged_sep!($separator, $($rest)*)
+    )
+  };
+  ($i:expr,  $separator:path, verify_input ! ($($rest:tt)*) ) => {
+    wrap_sep!($i,
+      $separator,
+      verify_input_sep!($separator, $($rest)*)
+    )
+  };
+  ($i:expr,  $separator:path, without_ws ! ($($rest:tt)*) ) => {
+    wrap_sep!($i,
+      $separator,
+      sep!($crate::no_sep, $($rest)*)
+    )
+  };
+  ($i:expr,  $separator:path, separator_errors ! ($($rest:tt)*) ) => {
+    separator_errors_sep!($i, $separator, $($rest)*)
+  };
+  ($i:expr,  $separator:path, no_ws ! ($submac:ident!( $($args:tt)* )) ) => {
+    $submac!($i, $($args)*)
+  };
+  ($i:expr,  $separator:path, no_ws ! ($f:expr) ) => {
+    nom_call!($i, $f)
+  };
+  ($i:expr,  $separator:path, offset_of ! ($($rest:tt)*) ) => {
+    wrap_sep!($i,
+      $separator,
+      offset_of_sep!($separator, $($rest)*)
+    )
+  };
+  ($i:expr,  $separator:path, peek_len ! ($($rest:tt)*) ) => {
+    peek_len_sep!($i, $separator, $($rest)*)
+  };
+  ($i:expr,  $separator:path, not ! ($($rest:tt)*) ) => {
+    not_sep!($i, $separator, $($rest)*)
+  };
+  ($i:expr,  $separator:path, cond_with_error ! ($($rest:tt)*) ) => {
+    cond_with_error_sep!($i, $separator, $($rest)*)
+  };
+  ($i:expr,  $separator:path, cond_reduce ! ($($rest:tt)*) ) => {
+    wrap_sep!($i,
+      $separator,
+      cond_reduce_sep!($separator, $($rest)*)
+    )
+  };
+  ($i:expr,  $separator:path, map_opt ! ($($rest:tt)*) ) => {
+    wrap_sep!($i,
+      $separator,
+      map_opt_sep!($separator, $($rest)*)
+    )
+  };
+  ($i:expr,  $separator:path, opt ! ($($rest:tt)*) ) => {
+    opt_sep!($i, $separator, $($rest)*)
+  };
+  ($i:expr,  $separator:path, opt_flag ! ($($rest:tt)*) ) => {
+    opt_flag_sep!($i, $separator, $($rest)*)
+  };
+  ($i:expr,  $separator:path, ws_tok ! ($separator2:path, $($rest:tt)*) ) => {
+    sep!($i, $separator2, $($rest)*)
+  };
+  ($i:expr,  $separator:path, many_same ! ($($rest:tt)*) ) => {
+    many_same_sep!($i, $separator, $($rest)*)
+  };
+  ($i:expr,  $separator:path, many_m_n ! ($($rest:tt)*) ) => {
+    many_m_n_sep!($i, $separator, $($rest)*)
+  };
+  ($i:expr,  $separator:path, recognize_trim ! ($($rest:tt)*) ) => {
+    wrap_sep!($i,
+      $separator,
+      recognize_trim_sep!($separator, $($rest)*)
+    )
+  };
+  ($i:expr,  $separator:path, recognize ! ($($rest:tt)*) ) => {
+    wrap_sep!($i,
+      $separator,
+      recognize_sep!($separator, $($rest)*)
+    )
+  };
+  ($i:expr,  $separator:path, number_list ! ($($rest:tt)*) ) => {
+    number_list_sep!($i, $separator, $($rest)*)
+  };
+  ($i:expr,  $separator:path, take_while_m_n ! ($($rest:tt)*) ) => {
+    wrap_sep!($i,
+      $separator,
+      nom_take_while_m_n!($($rest)*)
+    )
+  };
+  ($i:expr,  $separator:path, count ! ($($rest:tt)*) ) => {
+    wrap_sep!($i,
+      $separator,
+      count_sep!($separator, $($rest)*)
+    )
+  };
+  ($i:expr,  $separator:path, count_range ! ($($rest:tt)*) ) => {
+    count_range_sep!($i, $separator, $($rest)*)
+  };
+  ($i:expr,  $separator:path, fold_many0 ! ($($rest:tt)*) ) => {
+    fold_many0_sep!($i, $separator, $($rest)*)
+  };
+  ($i:expr,  $separator:path, fold_many0_while ! ($($rest:tt)*) ) => {
+    fold_many0_while_sep!($i, $separator, $($rest)*)
+  };
+  ($i:expr,  $separator:path, fold_many1 ! ($($rest:tt)*) ) => {
+    wrap_sep!($i,
+      $separator,
+      fold_many1_sep!($separator, $($rest)*)
+    )
+  };
+  ($i:expr,  $separator:path, many0_acc ! ($($rest:tt)*) ) => {
+    many0_acc_sep!($i, $separator, $($rest)*)
+  };
+  ($i:expr,  $separator:path, success ! ($value:expr) ) => {
+    $crate::lib::std::result::Result::Ok(($i, $value))
+  };
+  ($i:expr,  $separator:path, many0 ! ($($rest:tt)*) ) => {
+    many0_sep!($i, $separator, $($rest)*)
+  };
+  ($i:expr,  $separator:path, many1 ! ($($rest:tt)*) ) => {
+    many1_sep!($i, $separator, $($rest)*)
+  };
+  ($i:expr, $separator:path, complete!( $($args:tt)* )) => {
+    nom_complete!($i, sep!($separator, $($args)*))
+  };
+  ($i:expr, $separator:path, return_error!( $($args:tt)* )) => {
+    nom_return_error!($i, wrap_sep!($separator, $($args)*))
+  };
+//FIXME: missing separated_nonempty_list,
+// many_till, count_fixed, fold_many_m_n
+  ($i:expr, $separator:path, $submac:ident!( $($args:tt)* )) => {
+    wrap_sep!($i, $separator, $submac!($($args)*))
+  };
+  ($i:expr, $separator:path, $f:expr) => {
+    wrap_sep!($i, $separator, nom_call!($f))
+  };
+);
+
+/// error code returned by `sep_bounded!` when the separator consumes too much input
+pub const SEP_BOUNDED_ERROR: u32 = 0x5e9;
+
+/// error code returned by `many_same!` when an element is different from the first one
+pub const MANY_SAME_ERROR: u32 = 0x5ea;
+
+/// error code returned by `StickyNewlineSeparator` for a separator without a newline
+pub const STICKY_NEWLINE_ERROR: u32 = 0x5eb;
+
+/// error code returned by `separated_list_budget!` when there are too many elements
+pub const LIST_BUDGET_ERROR: u32 = 0x5ec;
+
+/// error code returned by `separator_errors!` when the separator fails
+pub const SEPARATOR_ERROR: u32 = 0x5ed;
+
+/// error code returned by `sp_strict_indent` for a run mixing tabs and spaces
+pub const MIXED_INDENT_ERROR: u32 = 0x5ee;
+
+/// used by `wrap_sep!` to check that the separator and the
+/// parser use the same input type. The separator error is
+/// converted to the parser error type with `Err::convert`,
+/// so it must implement `From`
+///
+/// ```compile_fail
+/// # #[macro_use] extern crate nom;
+/// # #[macro_use] extern crate nom_whitespace;
+/// # use nom::IResult;
+/// fn space(i: &[u8]) -> IResult<&[u8], &[u8], u64> {
+///   Ok((i, &i[..0]))
+/// }
+///
+/// fn token(i: &[u8]) -> IResult<&[u8], &[u8], u32> {
+///   Ok((&i[1..], &i[..1]))
+/// }
+///
+/// // there is no conversion from u64 to u32
+/// named!(parser<&[u8], &[u8], u32>, sep!(space, token));
+/// # fn main() {}
+/// ```
+#[doc(hidden)]
+#[inline(always)]
+pub fn unify_types<I, O, P, E, F>(_: &IResult<I, O, E>, _: &IResult<I, P, F>) {}
+
+/// a bracket group parsed by `balanced!` in `sep!` and `ws!`
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, PartialEq)]
+pub enum Balanced<O> {
+  /// an element, outside of any nested group
+  Leaf(O),
+  /// a nested group, with its elements and subgroups in order
+  Group(Vec<Balanced<O>>),
+}
+
+/// used by `balanced!` to parse a group and its subgroups recursively.
+/// An element that does not consume anything ends the group
+#[doc(hidden)]
+#[cfg(feature = "alloc")]
+pub fn balanced<I, O, E, A, B, FA, FB, FE>(input: I, open: &FA, close: &FB, element: &FE) -> IResult<I, Balanced<O>, E>
+where
+  I: Clone + InputLength,
+  FA: Fn(I) -> IResult<I, A, E>,
+  FB: Fn(I) -> IResult<I, B, E>,
+  FE: Fn(I) -> IResult<I, O, E>,
+{
+  let (mut input, _) = open(input)?;
+  let mut children = Vec::new();
+
+  loop {
+    let close_error = match close(input.clone()) {
+      Ok((i, _)) => return Ok((i, Balanced::Group(children))),
+      Err(Err::Error(e)) => e,
+      Err(e) => return Err(e),
+    };
+
+    match balanced(input.clone(), open, close, element) {
+      Ok((i, group)) => {
+        children.push(group);
+        input = i;
+        continue;
+      }
+      Err(Err::Error(_)) => {}
+      Err(e) => return Err(e),
+    }
+
+    // the group is not closed
+    match element(input.clone()) {
+      Ok((i, o)) if i.input_len() < input.input_len() => {
+        children.push(Balanced::Leaf(o));
+        input = i;
+      }
+      Ok(_) | Err(Err::Error(_)) => return Err(Err::Error(close_error)),
+      Err(e) => return Err(e),
+    }
+  }
+}
+
+use lib::std::cell::Cell;
+use lib::std::fmt::Debug;
+use lib::std::ops::RangeFrom;
+#[cfg(feature = "alloc")]
+use lib::std::vec::Vec;
+use lib::nom::{Context, Err, ErrorKind, IResult, Needed, need_more_err};
+use lib::nom::{AsChar, AtEof, Compare, CompareResult, FindToken, InputIter, InputLength, InputTake, InputTakeAtPosition, Offset, Slice};
+#[allow(unused_imports)]
+#[inline(always)]
+pub fn sp<'a, T>(input: T) -> IResult<T, T>
+where
+  T: InputTakeAtPosition,
+  <T as InputTakeAtPosition>::Item: AsChar + Clone,
+  &'a str: FindToken<<T as InputTakeAtPosition>::Item>,
+{
+  input.split_at_position(|item| {
+    let c = item.clone().as_char();
+    !(c == ' ' || c == '\t' || c == '\r' || c == '\n')
+  })
+  //this could be written as followed, but not using FindToken is faster
+  //eat_separator!(input, " \t\r\n")
+}
+
+/// like `sp`, but returns the number of line endings consumed
+///
+/// `\r\n` counts as one line ending, like a lone `\r` or `\n`
+///
+/// ```
+/// # use nom_whitespace::sp_crlf_aware;
+/// assert_eq!(sp_crlf_aware(&b" \r\n\t\n\r a"[..]), Ok((&b"a"[..], 3)));
+/// ```
+pub fn sp_crlf_aware<'a, T>(input: T) -> IResult<T, usize>
+where
+  T: InputTakeAtPosition + InputIter,
+  <T as InputTakeAtPosition>::Item: AsChar + Clone,
+  <T as InputIter>::Item: AsChar,
+  &'a str: FindToken<<T as InputTakeAtPosition>::Item>,
+{
+  let (remaining, consumed) = sp(input)?;
+
+  let mut lines = 0;
+  let mut after_cr = false;
+  for c in consumed.iter_elements() {
+    let c = c.as_char();
+    if c == '\r' || (c == '\n' && !after_cr) {
+      lines += 1;
+    }
+    after_cr = c == '\r';
+  }
+
+  Ok((remaining, lines))
+}
+
+/// state of `sp_resumable`, kept by the caller between the chunks
+/// of a partial input, for whitespace runs spanning several chunks
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct SpState {
+  run: usize,
+  pending: bool,
+}
+
+impl SpState {
+  pub fn new() -> SpState {
+    SpState::default()
+  }
+
+  /// length of the current whitespace run, over all the chunks seen
+  pub fn run_len(&self) -> usize {
+    self.run
+  }
+
+  /// true if the last chunk ended with whitespace, so the run can
+  /// continue in the next one
+  pub fn is_pending(&self) -> bool {
+    self.pending
+  }
+}
+
+/// like `sp`, but a partial input made only of whitespace is consumed
+/// entirely instead of returning `Incomplete`
+///
+/// it returns the length consumed in this chunk, and whether more
+/// whitespace might follow in the next one. `state` keeps track of
+/// the whole run: a new run starts on the first call after a run ended
+///
+/// ```
+/// # use nom_whitespace::{sp_resumable, SpState};
+/// let mut state = SpState::new();
+/// assert_eq!(sp_resumable(&mut state, " \t"), Ok(("", (2, true))));
+/// assert_eq!(sp_resumable(&mut state, "\na"), Ok(("a", (1, false))));
+/// assert_eq!(state.run_len(), 3);
+/// ```
+pub fn sp_resumable<T>(state: &mut SpState, input: T) -> IResult<T, (usize, bool)>
+where
+  T: InputTakeAtPosition + InputTake + InputLength,
+  <T as InputTakeAtPosition>::Item: AsChar + Clone,
+{
+  let res = input.split_at_position(|item| {
+    let c = item.clone().as_char();
+    !(c == ' ' || c == '\t' || c == '\r' || c == '\n')
+  });
+
+  let (remaining, consumed, more) = match res {
+    Ok((remaining, consumed)) => (remaining, consumed.input_len(), false),
+    Err(Err::Incomplete(_)) => {
+      let len = input.input_len();
+      (input.take_split(len).0, len, true)
+    }
+    Err(e) => return Err(e),
+  };
+
+  if !state.pending {
+    state.run = 0;
+  }
+  state.run += consumed;
+  state.pending = more;
+
+  Ok((remaining, (consumed, more)))
+}
+
+/// consumes a shebang line, like `#!/bin/sh\n`, if the input starts with one
+///
+/// it returns an empty slice if there is no shebang. The line ending
+/// is consumed too
+///
+/// ```
+/// # use nom_whitespace::shebang;
+/// assert_eq!(shebang(&b"#!/bin/sh\nls"[..]), Ok((&b"ls"[..], &b"#!/bin/sh\n"[..])));
+/// assert_eq!(shebang(&b"ls"[..]), Ok((&b"ls"[..], &b""[..])));
+/// ```
+pub fn shebang<T>(input: T) -> IResult<T, T>
+where
+  T: InputIter + InputTake + AtEof + InputLength,
+  <T as InputIter>::Item: AsChar,
+  <T as InputIter>::RawItem: AsChar,
+{
+  let mut it = input.iter_elements();
+  let start = (it.next().map(|c| c.as_char()), it.next().map(|c| c.as_char()));
+  match start {
+    (Some('#'), Some('!')) => match input.position(|c| c.as_char() == '\n') {
+      Some(end) => Ok(input.take_split(end + 1)),
+      None => {
+        if input.at_eof() {
+          let len = input.input_len();
+          Ok(input.take_split(len))
+        } else {
+          need_more_err(input, Needed::Unknown, ErrorKind::Tag)
+        }
+      }
+    },
+    (Some('#'), None) | (None, _) if !input.at_eof() => need_more_err(input, Needed::Size(2), ErrorKind::Tag),
+    _ => Ok(input.take_split(0)),
+  }
+}
+
+/// a separator parser, that can hold some state
+///
+/// separator functions implement it, and it can be used with `sep_with!`
+pub trait Separator<I> {
+  /// consumes the separator at the start of the input
+  fn parse(&self, input: I) -> IResult<I, I>;
+}
+
+impl<I, F> Separator<I> for F
+where
+  F: Fn(I) -> IResult<I, I>,
+{
+  fn parse(&self, input: I) -> IResult<I, I> {
+    self(input)
+  }
+}
+
+/// separator consuming the same characters as `sp`, but once it
+/// has consumed a newline, it fails on whitespace without a newline,
+/// with `ErrorKind::Custom(STICKY_NEWLINE_ERROR)`
+///
+/// the state is not reset on backtracking, so a new separator
+/// should be used for each parse
+#[derive(Debug, Default)]
+pub struct StickyNewlineSeparator {
+  multiline: Cell<bool>,
+}
+
+impl StickyNewlineSeparator {
+  pub fn new() -> StickyNewlineSeparator {
+    StickyNewlineSeparator::default()
+  }
+
+  /// true once a newline was consumed
+  pub fn is_multiline(&self) -> bool {
+    self.multiline.get()
+  }
+}
+
+impl<'a, I> Separator<I> for StickyNewlineSeparator
+where
+  I: InputTakeAtPosition + InputIter + InputLength + Clone,
+  <I as InputTakeAtPosition>::Item: AsChar + Clone,
+  <I as InputIter>::Item: AsChar,
+  <I as InputIter>::RawItem: AsChar,
+  &'a str: FindToken<<I as InputTakeAtPosition>::Item>,
+{
+  fn parse(&self, input: I) -> IResult<I, I> {
+    let (remaining, consumed) = sp(input.clone())?;
+    let newline = consumed.position(|c| c.as_char() == '\n').is_some();
+
+    if newline {
+      self.multiline.set(true);
+    } else if self.multiline.get() && consumed.input_len() > 0 {
+      return Err(Err::Error(Context::Code(input, ErrorKind::Custom(STICKY_NEWLINE_ERROR))));
+    }
+
+    Ok((remaining, consumed))
+  }
+}
+
+/// separator consuming whitespace with another separator, and a trailing
+/// comma before a closing tag
+///
+/// the comma, and what the separator consumes after it, are only consumed
+/// if they are followed by the closing tag, which is left in the input.
+/// Used with `sep_with!` around a bracketed `separated_list!`, it accepts a
+/// trailing comma without parsing an empty element.
+///
+/// A lone comma after the opening tag, as in `[,]`, is not consumed, so the
+/// list fails on it. The separator only sees its input, and tells the two
+/// cases apart by remembering where it saw the opening tag: the tag must
+/// be preceded by a call to the separator, as it is under `sep_with!`.
+/// The state is not reset on backtracking, so a new separator should be
+/// used for each parse
+///
+/// ```
+/// # #[macro_use] extern crate nom;
+/// # #[macro_use] extern crate nom_whitespace;
+/// # use nom::{alpha, IResult};
+/// # use nom::types::CompleteStr;
+/// use nom_whitespace::{sp, TrailingCommaSeparator};
+///
+/// fn array(i: CompleteStr) -> IResult<CompleteStr, Vec<CompleteStr>> {
+///   let separator = TrailingCommaSeparator::new(sp, "[", "]");
+///   sep_with!(i, &separator, delimited!(tag!("["), separated_list!(tag!(","), alpha), tag!("]")))
+/// }
+///
+/// # fn main() {
+/// assert_eq!(array(CompleteStr("[a, b,]")), Ok((CompleteStr(""), vec![CompleteStr("a"), CompleteStr("b")])));
+/// assert!(array(CompleteStr("[ , ]")).is_err());
+/// # }
+/// ```
+pub struct TrailingCommaSeparator<'a, S> {
+  separator: S,
+  open: &'a str,
+  close: &'a str,
+  /// length of the input left right after the last opening tag
+  open_end: Cell<Option<usize>>,
+}
+
+impl<'a, S> TrailingCommaSeparator<'a, S> {
+  pub fn new(separator: S, open: &'a str, close: &'a str) -> TrailingCommaSeparator<'a, S> {
+    TrailingCommaSeparator {
+      separator,
+      open,
+      close,
+      open_end: Cell::new(None),
+    }
+  }
+}
+
+impl<'a, I, S> Separator<I> for TrailingCommaSeparator<'a, S>
+where
+  S: Fn(I) -> IResult<I, I>,
+  I: Clone + InputLength + InputTake + Offset + Compare<&'a str>,
+{
+  fn parse(&self, input: I) -> IResult<I, I> {
+    let after_open = self.open_end.get() == Some(input.input_len());
+    let (mut rest, _) = (self.separator)(input.clone())?;
+
+    if after_open {
+      // still nothing but separators since the opening tag
+      self.open_end.set(Some(rest.input_len()));
+    } else if let CompareResult::Ok = rest.compare(",") {
+      if let Ok((after, _)) = (self.separator)(rest.take_split(1).0) {
+        if let CompareResult::Ok = after.compare(self.close) {
+          rest = after;
+        }
+      }
+    }
+
+    if let CompareResult::Ok = rest.compare(self.open) {
+      self.open_end.set(Some(rest.input_len() - self.open.len()));
+    }
+
+    let consumed = input.offset(&rest);
+    Ok((rest, input.take(consumed)))
+  }
+}
+
+/// a separator that consumes nothing
+///
+/// it stops at the first element of the input, without any scanning,
+/// so it can replace the separator in sections without whitespace, like
+/// with the `without_ws!` combinator of `sep!`. Like `sp`, it returns
+/// `Incomplete` on an empty partial input
+///
+/// ```
+/// # use nom_whitespace::no_sep;
+/// assert_eq!(no_sep(&b" a"[..]), Ok((&b" a"[..], &b""[..])));
+/// assert!(no_sep(&b""[..]).is_err());
+/// ```
+#[inline(always)]
+pub fn no_sep<T: InputTakeAtPosition>(input: T) -> IResult<T, T> {
+  input.split_at_position(|_| true)
+}
+
+/// consumes spaces and tabs, but not line endings
+#[inline]
+pub fn inline_sp<T>(input: T) -> IResult<T, T>
+where
+  T: InputTakeAtPosition,
+  <T as InputTakeAtPosition>::Item: AsChar + Clone,
+{
+  input.split_at_position(|item| {
+    let c = item.clone().as_char();
+    !(c == ' ' || c == '\t')
+  })
+}
+
+/// like `inline_sp`, but a backslash at the end of a line continues
+/// the line: `\\\n` and `\\\r\n` are consumed as whitespace, while a
+/// bare line ending stops the separator
+///
+/// ```
+/// # use nom_whitespace::sp_continuation;
+/// assert_eq!(sp_continuation(&b" \\\n b"[..]), Ok((&b"b"[..], &b" \\\n "[..])));
+/// assert_eq!(sp_continuation(&b" \n b"[..]), Ok((&b"\n b"[..], &b" "[..])));
+/// ```
+pub fn sp_continuation<T>(input: T) -> IResult<T, T>
+where
+  T: InputIter + InputTake + InputLength + AtEof,
+  <T as InputIter>::Item: AsChar,
+{
+  // position of the backslash, and whether a `\r` followed it
+  let mut continuation: Option<(usize, bool)> = None;
+  let mut end = None;
+
+  for (idx, c) in input.iter_indices() {
+    let c = c.as_char();
+    match continuation {
+      None => {
+        if c == '\\' {
+          continuation = Some((idx, false));
+        } else if c != ' ' && c != '\t' {
+          end = Some(idx);
+          break;
+        }
+      },
+      Some((start, after_cr)) => {
+        if c == '\n' {
+          continuation = None;
+        } else if c == '\r' && !after_cr {
+          continuation = Some((start, true));
+        } else {
+          end = Some(start);
+          break;
+        }
+      },
+    }
+  }
+
+  match end {
+    Some(end) => Ok(input.take_split(end)),
+    None => {
+      if input.at_eof() {
+        match continuation {
+          Some((start, _)) => Ok(input.take_split(start)),
+          None => {
+            let len = input.input_len();
+            Ok(input.take_split(len))
+          }
+        }
+      } else {
+        need_more_err(input, Needed::Size(1), ErrorKind::Space)
+      }
+    }
+  }
+}
+
+/// like `sp`, but fails with `ErrorKind::Custom(MIXED_INDENT_ERROR)` if
+/// a line of the whitespace run mixes tabs and spaces
+///
+/// each line ending starts a new run, so a line indented with spaces
+/// can follow a line indented with tabs. The error is returned at the
+/// start of the mixed run
+///
+/// ```
+/// # use nom_whitespace::sp_strict_indent;
+/// assert_eq!(sp_strict_indent(&b"\t\n  b"[..]), Ok((&b"b"[..], &b"\t\n  "[..])));
+/// assert!(sp_strict_indent(&b"\n\t b"[..]).is_err());
+/// ```
+pub fn sp_strict_indent<T>(input: T) -> IResult<T, T>
+where
+  T: InputIter + InputTake + InputLength + AtEof,
+  <T as InputIter>::Item: AsChar,
+{
+  // the indentation character of the current line, and where its run starts
+  let mut indent: Option<char> = None;
+  let mut run_start = 0;
+  let mut end = None;
+
+  for (idx, c) in input.iter_indices() {
+    let c = c.as_char();
+    match c {
+      ' ' | '\t' => match indent {
+        None => indent = Some(c),
+        Some(i) if i != c => {
+          let (run, _) = input.take_split(run_start);
+          return Err(Err::Error(Context::Code(run, ErrorKind::Custom(MIXED_INDENT_ERROR))));
+        },
+        Some(_) => {},
+      },
+      '\n' | '\r' => {
+        indent = None;
+        run_start = idx + 1;
+      },
+      _ => {
+        end = Some(idx);
+        break;
+      },
+    }
+  }
+
+  match end {
+    Some(end) => Ok(input.take_split(end)),
+    None => {
+      if input.at_eof() {
+        let len = input.input_len();
+        Ok(input.take_split(len))
+      } else {
+        need_more_err(input, Needed::Size(1), ErrorKind::Space)
+      }
+    }
+  }
+}
+
+/// like `sp`, but also consumes the Unicode bidirectional control characters
+/// (U+200E, U+200F, U+202A to U+202E, U+2066 to U+2069) and the zero width
+/// characters (U+200B to U+200D, U+2060, U+FEFF)
+///
+/// they cannot be seen in the text, but can change how it is displayed, so
+/// an identifier can look like another one. With this separator, they are
+/// discarded between tokens. They are only recognized in `str` inputs, and
+/// are still accepted inside the tokens by parsers like `alpha`
+///
+/// ```
+/// # use nom_whitespace::sp_sanitize;
+/// assert_eq!(sp_sanitize(" \u{202E}\u{200B} a"), Ok(("a", " \u{202E}\u{200B} ")));
+/// ```
+pub fn sp_sanitize<T>(input: T) -> IResult<T, T>
+where
+  T: InputTakeAtPosition,
+  <T as InputTakeAtPosition>::Item: AsChar + Clone,
+{
+  input.split_at_position(|item| {
+    !matches!(item.clone().as_char(),
+      ' ' | '\t' | '\r' | '\n'
+      | '\u{200B}'..='\u{200F}' | '\u{202A}'..='\u{202E}' | '\u{2066}'..='\u{2069}'
+      | '\u{2060}' | '\u{FEFF}')
+  })
+}
+
+/// recognizes a line ending, `\n` or `\r\n`, or the end of input
+///
+/// the end of input is only recognized if the input is complete,
+/// otherwise `Incomplete` is returned
+pub fn line_end<T>(input: T) -> IResult<T, T>
+where
+  T: InputIter + InputTake + AtEof,
+  <T as InputIter>::Item: AsChar,
+{
+  let mut it = input.iter_elements();
+  match it.next().map(|c| c.as_char()) {
+    Some('\n') => Ok(input.take_split(1)),
+    Some('\r') => match it.next().map(|c| c.as_char()) {
+      Some('\n') => Ok(input.take_split(2)),
+      None => need_more_err(input, Needed::Size(2), ErrorKind::CrLf),
+      _ => Err(Err::Error(Context::Code(input, ErrorKind::CrLf))),
+    },
+    None => {
+      if input.at_eof() {
+        Ok(input.take_split(0))
+      } else {
+        need_more_err(input, Needed::Size(1), ErrorKind::CrLf)
+      }
+    }
+    _ => Err(Err::Error(Context::Code(input, ErrorKind::CrLf))),
+  }
+}
+
+/// recognizes the end of a text file: optional spaces and tabs, then
+/// a single line ending (`\n` or `\r\n`) followed by the end of input
+///
+/// A missing line ending fails with `ErrorKind::CrLf`, and any input
+/// left after it with `ErrorKind::Eof`, like a partial input, where the
+/// end of input is not known yet. If `allow_blank_lines` is true,
+/// whitespace is accepted after the line ending, like empty lines
+///
+/// ```
+/// # extern crate nom;
+/// # extern crate nom_whitespace;
+/// # use nom::types::CompleteStr;
+/// use nom_whitespace::final_newline;
+///
+/// # fn main() {
+/// assert_eq!(final_newline(CompleteStr(" \n"), false), Ok((CompleteStr(""), CompleteStr(" \n"))));
+/// assert!(final_newline(CompleteStr(""), false).is_err());
+/// assert!(final_newline(CompleteStr("\n\n"), false).is_err());
+/// assert!(final_newline(CompleteStr("\n\n"), true).is_ok());
+/// # }
+/// ```
+pub fn final_newline<'a, T>(input: T, allow_blank_lines: bool) -> IResult<T, T>
+where
+  T: InputIter + InputTake + InputLength + InputTakeAtPosition + AtEof + Clone,
+  <T as InputIter>::Item: AsChar,
+  <T as InputTakeAtPosition>::Item: AsChar + Clone,
+  &'a str: FindToken<<T as InputTakeAtPosition>::Item>,
+{
+  let (i1, _) = inline_sp(input.clone())?;
+  if i1.input_len() == 0 && i1.at_eof() {
+    return Err(Err::Error(Context::Code(i1, ErrorKind::CrLf)));
+  }
+
+  let (mut i2, _) = line_end(i1)?;
+  if allow_blank_lines {
+    i2 = sp(i2)?.0;
+  }
+
+  if i2.input_len() != 0 || !i2.at_eof() {
+    return Err(Err::Error(Context::Code(i2, ErrorKind::Eof)));
+  }
+
+  let consumed = input.input_len();
+  Ok(input.take_split(consumed))
+}
+
+/// `require_final_newline!(I -> IResult<I,O>) => I -> IResult<I, O>`
+/// `require_final_newline!(I -> IResult<I,O>, bool) => I -> IResult<I, O>`
+///
+/// applies the parser, then checks with `final_newline` that the rest
+/// of the input is a single line ending, optionally preceded by spaces
+/// and tabs, like the POSIX definition of a text file. Blank lines are
+/// accepted after it if the second argument is true
+///
+/// ```
+/// # #[macro_use] extern crate nom;
+/// # #[macro_use] extern crate nom_whitespace;
+/// # use nom::alpha;
+/// # use nom::types::CompleteStr;
+/// named!(file<CompleteStr, CompleteStr>, require_final_newline!(alpha));
+///
+/// # fn main() {
+/// assert_eq!(file(CompleteStr("content\n")), Ok((CompleteStr(""), CompleteStr("content"))));
+/// assert!(file(CompleteStr("content")).is_err());
+/// # }
+/// ```
+#[macro_export(local_inner_macros)]
+macro_rules! require_final_newline (
+  ($i:expr, $submac:ident!( $($args:tt)* ), $allow_blank_lines:expr) => (
+    {
+      use $crate::lib::nom::{Convert,Err};
+      use $crate::lib::std::result::Result::*;
+
+      match $submac!($i, $($args)*) {
+        Err(e)      => Err(e),
+        Ok((i1, o)) => match $crate::final_newline(i1, $allow_blank_lines) {
+          Err(e)      => Err(Err::convert(e)),
+          Ok((i2, _)) => Ok((i2, o))
+        }
+      }
+    }
+  );
+  ($i:expr, $submac:ident!( $($args:tt)* )) => (
+    require_final_newline!($i, $submac!($($args)*), false)
+  );
+  ($i:expr, $f:expr, $allow_blank_lines:expr) => (
+    require_final_newline!($i, nom_call!($f), $allow_blank_lines)
+  );
+  ($i:expr, $f:expr) => (
+    require_final_newline!($i, nom_call!($f), false)
+  );
+);
+
+/// `eol_terminated!(I -> IResult<I,O>) => I -> IResult<I, O>`
+///
+/// applies the parser, skipping spaces and tabs between tokens,
+/// then expects the end of the line (`\n` or `\r\n`) or the end of
+/// input. The line ending is consumed
+///
+/// ```
+/// # #[macro_use] extern crate nom;
+/// # #[macro_use] extern crate nom_whitespace;
+/// # use nom::alpha;
+/// # use nom::types::CompleteStr;
+/// named!(line<CompleteStr, (CompleteStr, CompleteStr)>,
+///   eol_terminated!(pair!(alpha, alpha))
+/// );
+///
+/// # fn main() {
+/// assert_eq!(
+///   line(CompleteStr(" a\tb \r\nc d")),
+///   Ok((CompleteStr("c d"), (CompleteStr("a"), CompleteStr("b"))))
+/// );
+/// assert!(line(CompleteStr("a\nb")).is_err());
+/// # }
+/// ```
+#[macro_export(local_inner_macros)]
+macro_rules! eol_terminated (
+  ($i:expr, $($args:tt)*) => (
+    {
+      use $crate::lib::nom::Convert;
+      use $crate::lib::nom::Err;
+      use $crate::lib::std::result::Result::*;
+
+      match sep!($i, $crate::inline_sp, $($args)*) {
+        Err(e) => Err(e),
+        Ok((i1,o))    => {
+          match terminator!(i1) {
+            Err(e)      => Err(Err::convert(e)),
+            Ok((i2, _)) => Ok((i2, o))
+          }
+        }
+      }
+    }
+  )
+);
+
+/// `terminator!() => I -> IResult<I, I>`
+///
+/// skips spaces and tabs, then expects the end of the line (`\n`
+/// or `\r\n`) or the end of input, and returns the line ending.
+/// Like `eol_terminated!`, but usable on its own, like in `do_parse!`
+///
+/// ```
+/// # #[macro_use] extern crate nom;
+/// # #[macro_use] extern crate nom_whitespace;
+/// # use nom::alpha;
+/// # use nom::types::CompleteStr;
+/// # use nom_whitespace::inline_sp;
+/// named!(statement<CompleteStr, CompleteStr>,
+///   do_parse!(
+///     tag!("let") >>
+///     name: sep!(inline_sp, alpha) >>
+///     terminator!() >>
+///     (name)
+///   )
+/// );
+///
+/// # fn main() {
+/// assert_eq!(statement(CompleteStr("let a  \nlet b")), Ok((CompleteStr("let b"), CompleteStr("a"))));
+/// # }
+/// ```
+#[macro_export(local_inner_macros)]
+macro_rules! terminator (
+  ($i:expr,) => (
+    terminator!($i)
+  );
+  ($i:expr) => (
+    {
+      use $crate::lib::std::result::Result::*;
+
+      match $crate::inline_sp($i) {
+        Err(e)     => Err(e),
+        Ok((i1,_)) => $crate::line_end(i1)
+      }
+    }
+  );
+);
+
+/// function version of `ws_with!` for a single parser
+///
+/// returns a parser applying `separator`, then `parser`, then
+/// `separator` again. Like with `ws!`, the trailing separator
+/// returns `Incomplete` at the end of a partial input
+///
+/// ```
+/// # #[macro_use] extern crate nom;
+/// # extern crate nom_whitespace;
+/// # use nom::IResult;
+/// # use nom::types::CompleteStr;
+/// use nom_whitespace::{sp, ws_wrap};
+///
+/// fn a(i: CompleteStr) -> IResult<CompleteStr, CompleteStr> {
+///   tag!(i, "a")
+/// }
+///
+/// # fn main() {
+/// let parser = ws_wrap(sp, a);
+/// assert_eq!(parser(CompleteStr(" a b")), Ok((CompleteStr("b"), CompleteStr("a"))));
+/// # }
+/// ```
+pub fn ws_wrap<I, O, E, S, P>(separator: S, parser: P) -> impl Fn(I) -> IResult<I, O, E>
+where
+  S: Fn(I) -> IResult<I, I, E>,
+  P: Fn(I) -> IResult<I, O, E>,
+{
+  move |input: I| {
+    let (i1, _) = separator(input)?;
+    let (i2, o) = parser(i1)?;
+    let (i3, _) = separator(i2)?;
+    Ok((i3, o))
+  }
+}
+
+/// parses a shell like token
+///
+/// it is either a run of non whitespace characters, or a string
+/// between double quotes, that can contain whitespace. The quotes
+/// are not included in the result
+///
+/// ```
+/// # #[macro_use] extern crate nom;
+/// # #[macro_use] extern crate nom_whitespace;
+/// # use nom::types::CompleteStr;
+/// # use nom_whitespace::shell_token;
+/// named!(command<CompleteStr, (CompleteStr, CompleteStr)>,
+///   ws!(tuple!(shell_token, shell_token))
+/// );
+///
+/// # fn main() {
+/// # // with `ws_no_trailing`, the trailing space is left in place
+/// # if !cfg!(feature = "ws_no_trailing") {
+/// assert_eq!(
+///   command(CompleteStr(" echo \"hello world\" ")),
+///   Ok((CompleteStr(""), (CompleteStr("echo"), CompleteStr("hello world"))))
+/// );
+/// # } else {
+/// # assert_eq!(
+/// #   command(CompleteStr(" echo \"hello world\" ")),
+/// #   Ok((CompleteStr(" "), (CompleteStr("echo"), CompleteStr("hello world"))))
+/// # );
+/// # }
+/// # }
+/// ```
+pub fn shell_token<T>(input: T) -> IResult<T, T>
+where
+  T: InputTakeAtPosition + InputIter + InputTake + Slice<RangeFrom<usize>> + AtEof,
+  <T as InputTakeAtPosition>::Item: AsChar,
+  <T as InputIter>::Item: AsChar,
+  <T as InputIter>::RawItem: AsChar,
+{
+  let quoted = match input.iter_elements().next() {
+    Some(c) => c.as_char() == '"',
+    None => false,
+  };
+
+  if quoted {
+    let rest = input.slice(1..);
+    match rest.position(|c| c.as_char() == '"') {
+      Some(end) => {
+        let (remaining, token) = rest.take_split(end);
+        Ok((remaining.slice(1..), token))
+      }
+      None => need_more_err(input, Needed::Unknown, ErrorKind::Char),
+    }
+  } else {
+    input.split_at_position1(
+      |item| {
+        let c = item.as_char();
+        c == ' ' || c == '\t' || c == '\r' || c == '\n'
+      },
+      ErrorKind::IsNot,
+    )
+  }
+}
+
+#[doc(hidden)]
+pub fn check_no_ws_match<I, O, E>(input: &I, whitespace_only: bool, res: IResult<I, O, E>)
+where
+  I: Debug,
+  O: Debug,
+{
+  if !whitespace_only {
+    panic!("assert_no_ws_match!: the input {:?} is not whitespace only", input);
+  }
+  if let Ok((remaining, o)) = res {
+    panic!(
+      "assert_no_ws_match!: the parser matched the whitespace only input {:?}, returning {:?} (remaining: {:?})",
+      input, o, remaining
+    );
+  }
+}
+
+#[doc(hidden)]
+#[cfg(feature = "debug")]
+pub fn trace_print<I, O, E>(input: &I, res: &IResult<I, O, E>)
+where
+  I: ::std::fmt::Debug,
+  O: ::std::fmt::Debug,
+  E: ::std::fmt::Debug,
+{
+  eprintln!("separator at {:?}: {:?}", input, res);
+}
+
+/// `ws!(I -> IResult<I,O>) => I -> IResult<I, O>`
+///
+/// transforms a parser to automatically consume
+/// whitespace between each token. By default,
+/// it takes the following characters: `" \t\r\n"`.
+///
+/// If you need a whitespace parser consuming a
+/// different set of characters, you can make
+/// your own by reusing the `sep!` combinator.
+///
+/// To use `ws!`, pass your parser as argument:
+///
+/// ```
+/// # #[macro_use] extern crate nom;
+/// # #[macro_use] extern crate nom_whitespace;
+/// # fn main() {
+/// named!(tuple<&[u8], (&[u8], &[u8]) >,
+///   ws!(tuple!( take!(3), tag!("de") ))
+/// );
+///
+/// assert_eq!(
+///   tuple(&b" \t abc de fg"[..]),
+///  Ok((&b"fg"[..], (&b"abc"[..], &b"de"[..])))
+/// );
+/// # }
+/// ```
+///
+/// Whitespace after the last token is consumed too, unless the
+/// `ws_no_trailing` feature is enabled. That feature changes the
+/// behaviour of every `ws!` call in the dependency graph: parsers
+/// then return the trailing whitespace as remaining input, which
+/// matters for streaming input, or when the caller checks that
+/// the whole input was consumed. Use `ws_with!` or `sep!` to get
+/// one behaviour regardless of the feature.
+#[cfg(not(feature = "ws_no_trailing"))]
+#[macro_export(local_inner_macros)]
+macro_rules! ws (
+  ($i:expr, $($args:tt)*) => (
+    ws_with!($i, $crate::sp, $($args)*)
+  )
+);
+
+#[cfg(feature = "ws_no_trailing")]
+#[macro_export(local_inner_macros)]
+macro_rules! ws (
+  ($i:expr, $($args:tt)*) => (
+    sep!($i, $crate::sp, $($args)*)
+  )
+);
+
+/// `ws_with!(I -> IResult<I,I>, I -> IResult<I,O>) => I -> IResult<I, O>`
+///
+/// like `ws!`, but with a custom separator, that is also
+/// used to consume what follows the last token
+///
+/// ```
+/// # #[macro_use] extern crate nom;
+/// # #[macro_use] extern crate nom_whitespace;
+/// # use nom::types::CompleteStr;
+/// # use nom_whitespace::sp;
+/// named!(comment<CompleteStr, CompleteStr>, preceded!(tag!("#"), take_while!(|c| c != '\n')));
+/// named!(space<CompleteStr, CompleteStr>, alt_sep_fn!(sp, comment));
+///
+/// named!(tuple<CompleteStr, (CompleteStr, CompleteStr)>,
+///   ws_with!(space, tuple!(tag!("a"), tag!("b")))
+/// );
+///
+/// # fn main() {
+/// assert_eq!(
+///   tuple(CompleteStr("a # first\n b # last")),
+///   Ok((CompleteStr(""), (CompleteStr("a"), CompleteStr("b"))))
+/// );
+/// # }
+/// ```
+#[macro_export(local_inner_macros)]
+macro_rules! ws_with (
+  ($i:expr, $separator:path, $($args:tt)*) => (
+    {
+      use $crate::lib::nom::Convert;
+      use $crate::lib::nom::Err;
+      use $crate::lib::std::result::Result::*;
+
+      match sep!($i, $separator, $($args)*) {
+        Err(e) => Err(e),
+        Ok((i1,o))    => {
+          match ($separator)(i1) {
+            Err(e) => Err(Err::convert(e)),
+            Ok((i2,_))    => Ok((i2, o))
+          }
+        }
+      }
+    }
+  )
+);
+
+/// `two_phase!(I -> IResult<I,I>, I -> IResult<I,H>, I -> IResult<I,I>, I -> IResult<I,B>) => I -> IResult<I, (H, B)>`
+///
+/// applies a header parser with a first separator, then a body parser
+/// with a second one, like `sep!`, and returns both results. As with
+/// `sep!`, the whitespace after the body is not consumed
+///
+/// ```
+/// # #[macro_use] extern crate nom;
+/// # #[macro_use] extern crate nom_whitespace;
+/// # use nom::{alpha, digit};
+/// # use nom::types::CompleteStr;
+/// # use nom_whitespace::{inline_sp, line_end, sp};
+/// named!(message<CompleteStr, ((CompleteStr, CompleteStr), (CompleteStr, CompleteStr))>,
+///   two_phase!(
+///     inline_sp, terminated!(pair!(alpha, alpha), line_end),
+///     sp, pair!(digit, digit)
+///   )
+/// );
+///
+/// # fn main() {
+/// assert_eq!(
+///   message(CompleteStr("size  big \n 1\n\n2")),
+///   Ok((CompleteStr(""), ((CompleteStr("size"), CompleteStr("big")), (CompleteStr("1"), CompleteStr("2")))))
+/// );
+/// # }
+/// ```
+#[macro_export(local_inner_macros)]
+macro_rules! two_phase (
+  ($i:expr, $header_sep:path, $submac:ident!( $($args:tt)* ), $body_sep:path, $submac2:ident!( $($args2:tt)* )) => (
+    {
+      use $crate::lib::std::result::Result::*;
+
+      match sep!($i, $header_sep, $submac!($($args)*)) {
+        Err(e)     => Err(e),
+        Ok((i1,h)) => match sep!(i1, $body_sep, $submac2!($($args2)*)) {
+          Err(e)     => Err(e),
+          Ok((i2,b)) => Ok((i2, (h, b)))
+        }
+      }
+    }
+  );
+  ($i:expr, $header_sep:path, $submac:ident!( $($args:tt)* ), $body_sep:path, $g:expr) => (
+    two_phase!($i, $header_sep, $submac!($($args)*), $body_sep, nom_call!($g))
+  );
+  ($i:expr, $header_sep:path, $f:expr, $body_sep:path, $($rest:tt)*) => (
+    two_phase!($i, $header_sep, nom_call!($f), $body_sep, $($rest)*)
+  );
+);
+
+/// `assert_no_ws_match!(I -> IResult<I,O>, I)`
+///
+/// test helper asserting that a parser does not succeed on a
+/// whitespace only input. It catches parsers matching nothing, like
+/// `ws!(many0!(...))`, where an empty match is not expected. It
+/// panics if the input is not whitespace only (as recognized by
+/// `sp`), so it should be used with complete input types
+///
+/// ```
+/// # #[macro_use] extern crate nom;
+/// # #[macro_use] extern crate nom_whitespace;
+/// # use nom::types::CompleteStr;
+/// named!(list<CompleteStr, Vec<CompleteStr> >, ws!(many1!(tag!("a"))));
+///
+/// # fn main() {
+/// assert_no_ws_match!(list, CompleteStr(" \t\n "));
+/// # }
+/// ```
+#[macro_export(local_inner_macros)]
+macro_rules! assert_no_ws_match (
+  ($parser:expr, $input:expr) => (
+    {
+      use $crate::lib::std::result::Result::*;
+      use $crate::lib::nom::InputLength;
+
+      let input = $input;
+      let whitespace_only = match $crate::sp(input.clone()) {
+        Ok((i, _)) => i.input_len() == 0,
+        Err(_)     => false,
+      };
+      $crate::check_no_ws_match(&input, whitespace_only, $parser(input.clone()))
+    }
+  );
+);
+
+/// `ws_shebang!(I -> IResult<I,O>) => I -> IResult<I, O>`
+///
+/// like `ws!`, but first skips a shebang line (see `shebang`). The
+/// shebang is only recognized at the very start of the input, not
+/// after whitespace, and not anywhere else as a separator
+///
+/// ```
+/// # #[macro_use] extern crate nom;
+/// # #[macro_use] extern crate nom_whitespace;
+/// # use nom::alpha;
+/// # use nom::types::CompleteStr;
+/// named!(script<CompleteStr, Vec<CompleteStr> >, ws_shebang!(many0!(alpha)));
+///
+/// # fn main() {
+/// assert_eq!(
+///   script(CompleteStr("#!/usr/bin/env x\n a b")),
+///   Ok((CompleteStr(""), vec![CompleteStr("a"), CompleteStr("b")]))
+/// );
+/// # }
+/// ```
+#[macro_export(local_inner_macros)]
+macro_rules! ws_shebang (
+  ($i:expr, $($args:tt)*) => (
+    {
+      use $crate::lib::nom::{Convert,Err};
+      use $crate::lib::std::result::Result::*;
+
+      match $crate::shebang($i) {
+        Err(e)     => Err(Err::convert(e)),
+        Ok((i1,_)) => ws!(i1, $($args)*)
+      }
+    }
+  )
+);
+
+/// `ws_recognize!(I -> IResult<I,O>) => I -> IResult<I, I>`
+///
+/// like `ws!`, but returns the input consumed by the parser,
+/// from the start of the first token to the end of the last
+/// one, without the leading and trailing whitespace. Unlike
+/// `ws!`, the trailing whitespace is always consumed
+///
+/// ```
+/// # #[macro_use] extern crate nom;
+/// # #[macro_use] extern crate nom_whitespace;
+/// # use nom::types::CompleteStr;
+/// named!(span<CompleteStr, CompleteStr>,
+///   ws_recognize!(tuple!(tag!("a"), tag!("b")))
+/// );
+///
+/// # fn main() {
+/// assert_eq!(span(CompleteStr("  a b  ")), Ok((CompleteStr(""), CompleteStr("a b"))));
+/// # }
+/// ```
+#[macro_export(local_inner_macros)]
+macro_rules! ws_recognize (
+  ($i:expr, $($args:tt)*) => (
+    {
+      use $crate::lib::nom::{Convert,Err,InputTake,Offset};
+      use $crate::lib::std::result::Result::*;
+
+      match $crate::sp($i) {
+        Err(e)      => Err(Err::convert(e)),
+        Ok((i1, _)) => {
+          match sep!(i1.clone(), $crate::sp, $($args)*) {
+            Err(e)      => Err(e),
+            Ok((i2, _)) => {
+              let consumed = i1.take(i1.offset(&i2));
+              match $crate::sp(i2) {
+                Err(e)      => Err(Err::convert(e)),
+                Ok((i3, _)) => Ok((i3, consumed))
+              }
+            }
+          }
+        }
+      }
+    }
+  )
+);
+
+/// `sep_with!(&Separator<I>, I -> IResult<I,O>) => I -> IResult<I, O>`
+///
+/// like `sep!`, but the separator is a reference to a value implementing
+/// the `Separator` trait, which can hold some state
+///
+/// ```
+/// # #[macro_use] extern crate nom;
+/// # #[macro_use] extern crate nom_whitespace;
+/// # use nom::IResult;
+/// # use nom::types::CompleteStr;
+/// # use nom::alpha;
+/// use nom_whitespace::StickyNewlineSeparator;
+///
+/// fn words(i: CompleteStr) -> IResult<CompleteStr, Vec<CompleteStr>> {
+///   let separator = StickyNewlineSeparator::new();
+///   sep_with!(i, &separator, many0!(alpha))
+/// }
+///
+/// # fn main() {
+/// assert_eq!(
+///   words(CompleteStr("a b\nc\nd")),
+///   Ok((CompleteStr(""), vec![CompleteStr("a"), CompleteStr("b"), CompleteStr("c"), CompleteStr("d")]))
+/// );
+/// // once a newline was seen, spaces alone do not separate anymore
+/// assert_eq!(
+///   words(CompleteStr("a b\nc d")),
+///   Ok((CompleteStr(" d"), vec![CompleteStr("a"), CompleteStr("b"), CompleteStr("c")]))
+/// );
+/// # }
+/// ```
+#[macro_export(local_inner_macros)]
+macro_rules! sep_with (
+  ($i:expr, $separator:expr, $($args:tt)*) => (
+    {
+      let separator = $separator;
+      let separator_fn = |input| $crate::Separator::parse(separator, input);
+
+      sep!($i, separator_fn, $($args)*)
+    }
+  )
+);
+
+/// `ws_lenient!(I -> IResult<I, Option<R>>, &mut Vec<R>, I -> IResult<I,O>) => I -> IResult<I, O>`
+///
+/// like `ws!`, but with a separator that can recover from malformed
+/// input (like an unterminated comment) instead of failing.
+///
+/// The separator returns `Some(report)` when it had to skip over
+/// something invalid, and `None` otherwise. Reports are pushed to the
+/// vector and the separator is called again, so a single run of
+/// separators can yield multiple reports. When returning a report, the
+/// separator should have consumed the invalid part of the input: if it
+/// did not consume anything, the report is kept but the separator is
+/// not called again.
+///
+/// ```
+/// # #[macro_use] extern crate nom;
+/// # #[macro_use] extern crate nom_whitespace;
+/// # use nom::IResult;
+/// # use nom::types::CompleteStr;
+/// # use nom_whitespace::sp;
+/// // skips whitespace, and a `!` which is reported
+/// fn bangs(input: CompleteStr) -> IResult<CompleteStr, Option<usize>> {
+///   let (i, _) = sp(input)?;
+///   if i.starts_with("!") {
+///     Ok((CompleteStr(&i[1..]), Some(i.len())))
+///   } else {
+///     Ok((i, None))
+///   }
+/// }
+///
+/// fn parser<'a>(i: CompleteStr<'a>, reports: &mut Vec<usize>)
+///   -> IResult<CompleteStr<'a>, (CompleteStr<'a>, CompleteStr<'a>)> {
+///   ws_lenient!(i, bangs, reports, tuple!(tag!("a"), tag!("b")))
+/// }
+///
+/// # fn main() {
+/// let mut reports = Vec::new();
+/// assert_eq!(
+///   parser(CompleteStr(" a ! b !"), &mut reports),
+///   Ok((CompleteStr(""), (CompleteStr("a"), CompleteStr("b"))))
+/// );
+/// assert_eq!(reports, vec![5, 1]);
+/// # }
+/// ```
+#[macro_export(local_inner_macros)]
+macro_rules! ws_lenient (
+  ($i:expr, $separator:path, $reports:expr, $($args:tt)*) => (
+    {
+      use $crate::lib::std::cell::RefCell;
+      use $crate::lib::std::option::Option::*;
+      use $crate::lib::std::result::Result::*;
+      use $crate::lib::nom::InputLength;
+
+      let reports = RefCell::new($reports);
+      let lenient_sep = |input| {
+        let mut input = input;
+        loop {
+          // the input type is not known yet, so no method call syntax
+          let len = InputLength::input_len(&input);
+          match $separator(input) {
+            Ok((i, Some(report))) => {
+              reports.borrow_mut().push(report);
+              // a report without progress would be returned forever
+              if InputLength::input_len(&i) == len {
+                return Ok((i, ()));
+              }
+              input = i;
+            },
+            Ok((i, None)) => return Ok((i, ())),
+            Err(e)        => return Err(e),
+          }
+        }
+      };
+
+      ws_with!($i, lenient_sep, $($args)*)
+    }
+  )
+);
+
+#[cfg(test)]
+#[allow(dead_code)]
+mod tests {
+  #[cfg(feature = "alloc")]
+  use lib::std::string::{String, ToString};
+  use nom::{Err, IResult, Needed};
+  use super::sp;
+  use nom::ErrorKind;
+  use nom::types::{CompleteByteSlice, CompleteStr};
+
+  // the input left by `ws!` after the last token, from the input left
+  // before the trailing whitespace, which is only consumed without
+  // the `ws_no_trailing` feature
+  fn unconsumed(before_trailing: &str) -> &str {
+    if cfg!(feature = "ws_no_trailing") {
+      before_trailing
+    } else {
+      before_trailing.trim_start_matches(&[' ', '\t', '\r', '\n'][..])
+    }
+  }
+
+  fn unconsumed_bytes(before_trailing: &[u8]) -> &[u8] {
+    if cfg!(feature = "ws_no_trailing") {
+      before_trailing
+    } else {
+      let start = before_trailing.iter().position(|c| !b" \t\r\n".contains(c)).unwrap_or(before_trailing.len());
+      &before_trailing[start..]
+    }
+  }
+
+  #[test]
+  fn spaaaaace() {
+    assert_eq!(sp(&b" \t abc "[..]), Ok((&b"abc "[..], &b" \t "[..])));
+  }
+
+  #[test]
+  fn tag() {
+    named!(abc, ws!(tag!("abc")));
+
+    assert_eq!(abc(&b" \t abc def"[..]), Ok((unconsumed_bytes(b" def"), &b"abc"[..])));
+  }
+
+  #[test]
+  fn pair() {
+    named!(pair_2<&[u8], (&[u8], &[u8]) >,
+      ws!(pair!( take!(3), tag!("de") ))
+    );
+
+    assert_eq!(
+      pair_2(&b" \t abc de fg"[..]),
+      Ok((unconsumed_bytes(b" fg"), (&b"abc"[..], &b"de"[..])))
+    );
+  }
+
+  #[test]
+  fn preceded() {
+    named!(prec<&[u8], &[u8] >,
+      ws!(preceded!( take!(3), tag!("de") ))
+    );
+
+    assert_eq!(prec(&b" \t abc de fg"[..]), Ok((unconsumed_bytes(b" fg"), &b"de"[..])));
+  }
+
+  #[test]
+  fn terminated() {
+    named!(term<&[u8], &[u8] >,
+      ws!(terminated!( take!(3), tag!("de") ))
+    );
+
+    assert_eq!(term(&b" \t abc de fg"[..]), Ok((unconsumed_bytes(b" fg"), &b"abc"[..])));
+  }
+
+  #[test]
+  fn tuple() {
+    //trace_macros!(true);
+    named!(tuple_2<&[u8], (&[u8], &[u8]) >,
+      ws!(tuple!( take!(3), tag!("de") ))
+    );
+    //trace_macros!(false);
+
+    assert_eq!(
+      tuple_2(&b" \t abc de fg"[..]),
+      Ok((unconsumed_bytes(b" fg"), (&b"abc"[..], &b"de"[..])))
+    );
+  }
+
+  #[test]
+  fn levels() {
+    //trace_macros!(true);
+    named!(level_2<&[u8], (&[u8], (&[u8], &[u8])) >,
+      ws!(pair!(take!(3), tuple!( tag!("de"), tag!("fg ") )))
+    );
+    //trace_macros!(false);
+
+    assert_eq!(
+      level_2(&b" \t abc de fg \t hi "[..]),
+      Ok((unconsumed_bytes(b"\t hi "), (&b"abc"[..], (&b"de"[..], &b"fg "[..]))))
+    );
+  }
+
+  #[test]
+  fn do_parse() {
+    fn ret_int1(i: &[u8]) -> IResult<&[u8], u8> {
+      Ok((i, 1))
+    }
+    fn ret_int2(i: &[u8]) -> IResult<&[u8], u8> {
+      Ok((i, 2))
+    }
+
+    //trace_macros!(true);
+    named!(do_parser<&[u8], (u8, u8)>,
+      ws!(do_parse!(
+        tag!("abcd")       >>
+        opt!(tag!("abcd")) >>
+        aa: ret_int1       >>
+        tag!("efgh")       >>
+        bb: ret_int2       >>
+        tag!("efgh")       >>
+        (aa, bb)
+      ))
+    );
+
+    //trace_macros!(false);
+
+    assert_eq!(
+      do_parser(&b"abcd abcd\tefghefghX"[..]),
+      Ok((&b"X"[..], (1, 2)))
+    );
+    assert_eq!(
+      do_parser(&b"abcd\tefgh      efgh X"[..]),
+      Ok((unconsumed_bytes(b" X"), (1, 2)))
+    );
+    assert_eq!(
+      do_parser(&b"abcd  ab"[..]),
+      Err(Err::Incomplete(Needed::Size(4)))
+    );
+    assert_eq!(
+      do_parser(&b" abcd\tefgh\tef"[..]),
+      Err(Err::Incomplete(Needed::Size(4)))
+    );
+  }
+
+  #[test]
+  fn permutation() {
+    //trace_macros!(true);
+    named!(
+      perm<(&[u8], &[u8], &[u8])>,
+      ws!(permutation!(tag!("abcd"), tag!("efg"), tag!("hi")))
+    );
+    //trace_macros!(false);
+
+    let expected = (&b"abcd"[..], &b"efg"[..], &b"hi"[..]);
+
+    let a = &b"abcd\tefg \thijk"[..];
+    assert_eq!(perm(a), Ok((&b"jk"[..], expected)));
+    let b = &b"  efg  \tabcdhi jk"[..];
+    assert_eq!(perm(b), Ok((unconsumed_bytes(b" jk"), expected)));
+    let c = &b" hi   efg\tabcdjk"[..];
+    assert_eq!(perm(c), Ok((&b"jk"[..], expected)));
+
+    let d = &b"efg  xyzabcdefghi"[..];
+    assert_eq!(
+      perm(d),
+      Err(Err::Error(nom_ws_error_node_position!(
+        &b"efg  xyzabcdefghi"[..],
+        ErrorKind::Permutation,
+        nom_ws_error_position!(&b"  xyzabcdefghi"[..], ErrorKind::Permutation)
+      )))
+    );
+
+    let e = &b" efg \tabc"[..];
+    assert_eq!(perm(e), Err(Err::Incomplete(Needed::Size(4))));
+  }
+
+  #[cfg(feature = "alloc")]
+  #[derive(Debug, Clone, PartialEq)]
+  pub struct ErrorStr(String);
+
+  #[cfg(feature = "alloc")]
+  impl From<u32> for ErrorStr {
+    fn from(i: u32) -> Self {
+      ErrorStr(format!("custom error code: {}", i))
+    }
+  }
+
+  #[cfg(feature = "alloc")]
+  impl<'a> From<&'a str> for ErrorStr {
+    fn from(i: &'a str) -> Self {
+      ErrorStr(format!("custom error message: {}", i))
+    }
+  }
+
+  #[cfg(feature = "alloc")]
+  #[test]
+  fn alt() {
+    fn work(input: &[u8]) -> IResult<&[u8], &[u8], ErrorStr> {
+      Ok((&b""[..], input))
+    }
+
+    #[allow(unused_variables)]
+    fn dont_work(input: &[u8]) -> IResult<&[u8], &[u8], ErrorStr> {
+      use nom::Context;
+      Err(Err::Error(Context::Code(
+        &b""[..],
+        ErrorKind::Custom(ErrorStr("abcd".to_string())),
+      )))
+    }
+
+    fn work2(input: &[u8]) -> IResult<&[u8], &[u8], ErrorStr> {
+      Ok((input, &b""[..]))
+    }
+
+    fn alt1(i: &[u8]) -> IResult<&[u8], &[u8], ErrorStr> {
+      alt!(i, dont_work | dont_work)
+    }
+    fn alt2(i: &[u8]) -> IResult<&[u8], &[u8], ErrorStr> {
+      alt!(i, dont_work | work)
+    }
+    fn alt3(i: &[u8]) -> IResult<&[u8], &[u8], ErrorStr> {
+      alt!(i, dont_work | dont_work | work2 | dont_work)
+    }
+
+    let a = &b"\tabcd"[..];
+    assert_eq!(
+      alt1(a),
+      Err(Err::Error(nom_ws_error_position!(a, ErrorKind::Alt::<ErrorStr>)))
+    );
+    assert_eq!(alt2(a), Ok((&b""[..], a)));
+    assert_eq!(alt3(a), Ok((a, &b""[..])));
+
+    named!(alt4<CompleteStr, CompleteStr>, ws!(alt!(tag!("abcd") | tag!("efgh"))));
+    assert_eq!(
+      alt4(CompleteStr("\tabcd")),
+      Ok((CompleteStr(""), CompleteStr(r"abcd")))
+    );
+    assert_eq!(
+      alt4(CompleteStr("  efgh ")),
+      Ok((CompleteStr(unconsumed(" ")), CompleteStr("efgh")))
+    );
+
+    // test the alternative syntax
+    named!(alt5<CompleteStr, bool>, ws!(alt!(tag!("abcd") => { |_| false } | tag!("efgh") => { |_| true })));
+    assert_eq!(alt5(CompleteStr("\tabcd")), Ok((CompleteStr(""), false)));
+    assert_eq!(alt5(CompleteStr("  efgh ")), Ok((CompleteStr(unconsumed(" ")), true)));
+  }
+
+  /*FIXME: alt_complete works, but ws will return Incomplete on end of input
+  #[test]
+  fn alt_complete() {
+    named!(ac<&[u8], &[u8]>,
+      ws!(alt_complete!(tag!("abcd") | tag!("ef") | tag!("ghi") | tag!("kl")))
+    );
+
+    let a = &b""[..];
+    assert_eq!(ac(a), Err(Err::Error(nom_ws_error_position!(a, ErrorKind::Alt))));
+    let a = &b" \tef "[..];
+    assert_eq!(ac(a),Ok((&b""[..], &b"ef"[..])));
+    let a = &b" cde"[..];
+    assert_eq!(ac(a), Err(Err::Error(nom_ws_error_position!(&a[1..], ErrorKind::Alt))));
+  }
+  */
+
+  #[allow(unused_variables)]
+  #[test]
+  fn switch() {
+    named!(sw<CompleteStr,CompleteStr>,
+      ws!(switch!(take!(4),
+        CompleteStr("abcd") => take!(2) |
+        CompleteStr("efgh") => take!(4)
+      ))
+    );
+
+    let a = CompleteStr(" abcd ef gh");
+    assert_eq!(sw(a), Ok((CompleteStr(unconsumed(" gh")), CompleteStr("ef"))));
+
+    let b = CompleteStr("\tefgh ijkl ");
+    assert_eq!(sw(b), Ok((CompleteStr(unconsumed(" ")), CompleteStr("ijkl"))));
+    let c = CompleteStr("afghijkl");
+    assert_eq!(
+      sw(c),
+      Err(Err::Error(nom_ws_error_position!(
+        CompleteStr("afghijkl"),
+        ErrorKind::Switch
+      )))
+    );
+  }
+
+  #[test]
+  fn fold_many0_while() {
+    named!(number<CompleteStr, u32>, map_res!(nom::digit, |d: CompleteStr| d.parse::<u32>()));
+    named!(sum_to_3<CompleteStr, u32>,
+      sep!(sp, fold_many0_while!(number, 0, |acc: u32, n: u32| {
+        let sum = acc + n;
+        if sum > 3 { std::ops::ControlFlow::Break(sum) } else { std::ops::ControlFlow::Continue(sum) }
+      }))
+    );
+
+    assert_eq!(sum_to_3(CompleteStr(" 1 2 3 4")), Ok((CompleteStr(" 4"), 6)));
+    assert_eq!(sum_to_3(CompleteStr(" 1 2 a")), Ok((CompleteStr(" a"), 3)));
+    assert_eq!(sum_to_3(CompleteStr(" 5")), Ok((CompleteStr(""), 5)));
+    assert_eq!(sum_to_3(CompleteStr(" a")), Ok((CompleteStr(" a"), 0)));
+  }
+
+  #[test]
+  fn fold_many1_index() {
+    use nom::digit;
+
+    // any token must be a number
+    fn number(i: CompleteStr) -> IResult<CompleteStr, u32> {
+      match digit(i) {
+        Ok((i, d))                         => Ok((i, d.parse().unwrap())),
+        Err(Err::Error(e)) if !i.is_empty() => Err(Err::Failure(e)),
+        Err(e)                             => Err(e),
+      }
+    }
+
+    named!(sum<CompleteStr, u32>, ws!(fold_many1!(number, 0, |acc, n| acc + n)));
+
+    assert_eq!(sum(CompleteStr(" 1 2 3 ")), Ok((CompleteStr(unconsumed(" ")), 6)));
+    assert_eq!(
+      sum(CompleteStr(" 1 2 x")),
+      Err(Err::Failure(error_position!(CompleteStr("x"), ErrorKind::Custom(2))))
+    );
+    assert_eq!(
+      sum(CompleteStr(" x")),
+      Err(Err::Failure(error_position!(CompleteStr("x"), ErrorKind::Many1)))
+    );
+
+    named!(sum_a<CompleteStr, usize>, ws!(fold_many1!(tag!("a"), 0, |acc, _| acc + 1)));
+    assert_eq!(sum_a(CompleteStr("a a b")), Ok((CompleteStr(unconsumed(" b")), 2)));
+  }
+
+  #[test]
+  fn switch_default() {
+    use nom::alpha;
+
+    named!(sw<CompleteStr, CompleteStr>,
+      ws!(switch!(alpha,
+        CompleteStr("a") => tag!("1") |
+        CompleteStr("b") => tag!("2") |
+        _ => recognize!(many1!(alpha))
+      ))
+    );
+
+    assert_eq!(sw(CompleteStr(" a 1")), Ok((CompleteStr(""), CompleteStr("1"))));
+    assert_eq!(sw(CompleteStr("b\t2 ")), Ok((CompleteStr(unconsumed(" ")), CompleteStr("2"))));
+    assert_eq!(sw(CompleteStr(" c  d e ")), Ok((CompleteStr(unconsumed(" ")), CompleteStr("d e"))));
+    assert_eq!(
+      sw(CompleteStr(" c 1")),
+      Err(Err::Error(nom_ws_error_node_position!(
+        CompleteStr("c 1"),
+        ErrorKind::Switch,
+        nom_ws_error_position!(CompleteStr("1"), ErrorKind::Many1)
+      )))
+    );
+  }
+
+  named!(str_parse(&str) -> &str, ws!(tag!("test")));
+  #[allow(unused_variables)]
+  #[test]
+  fn str_test() {
+    assert_eq!(str_parse(" \n   test\t a\nb"), Ok((unconsumed("\t a\nb"), "test")));
+  }
+
+  // test whitespace parser generation for alt
+  named!(space, tag!(" "));
+  #[cfg(feature = "alloc")]
+  named!(pipeline_statement<&[u8], ()>,
+    ws!(
+      do_parse!(
+      tag!("pipeline") >>
+      attributes: delimited!(char!('{'),
+                             separated_list!(char!(','), alt!(
+                               space |
+                               space
+                             )),
+                             char!('}')) >>
+
+      ({
+        let _ = attributes;
+      })
+    )
+  )
+  );
+
+  #[cfg(feature = "alloc")]
+  named!(
+    fail<&[u8]>,
+    map!(many_till!(take!(1), ws!(tag!("."))), |(r, _)| r[0])
+  );
+
+  // skips whitespace and `/* */` comments. A comment that is not closed
+  // on its line is reported, and skipped up to the end of the line
+  fn lenient_comments(input: CompleteStr) -> IResult<CompleteStr, Option<Err<CompleteStr>>> {
+    let (i, _) = sp(input)?;
+    if !i.starts_with("/*") {
+      return Ok((i, None));
+    }
+
+    let line_end = i.find('\n').unwrap_or_else(|| i.len());
+    match i[..line_end].find("*/") {
+      Some(end) => lenient_comments(CompleteStr(&i[end + 2..])),
+      None => Ok((
+        CompleteStr(&i[line_end..]),
+        Some(Err::Error(nom_ws_error_position!(i, ErrorKind::Custom(0)))),
+      )),
+    }
+  }
+
+  #[cfg(feature = "alloc")]
+  #[test]
+  fn lenient() {
+    use lib::std::vec::Vec;
+
+    fn parser<'a>(
+      i: CompleteStr<'a>,
+      reports: &mut Vec<Err<CompleteStr<'a>>>,
+    ) -> IResult<CompleteStr<'a>, (CompleteStr<'a>, CompleteStr<'a>, CompleteStr<'a>)> {
+      ws_lenient!(i, lenient_comments, reports, tuple!(tag!("a"), tag!("b"), tag!("c")))
+    }
+
+    let mut reports = Vec::new();
+    assert_eq!(
+      parser(CompleteStr("/* ok */ a /* open\n b /* ok */ c /* open again\n"), &mut reports),
+      Ok((CompleteStr(""), (CompleteStr("a"), CompleteStr("b"), CompleteStr("c"))))
+    );
+    assert_eq!(
+      reports,
+      vec![
+        Err::Error(nom_ws_error_position!(CompleteStr("/* open\n b /* ok */ c /* open again\n"), ErrorKind::Custom(0))),
+        Err::Error(nom_ws_error_position!(CompleteStr("/* open again\n"), ErrorKind::Custom(0))),
+      ]
+    );
+
+    let mut reports = Vec::new();
+    assert_eq!(
+      parser(CompleteStr(" a b c"), &mut reports),
+      Ok((CompleteStr(""), (CompleteStr("a"), CompleteStr("b"), CompleteStr("c"))))
+    );
+    assert!(reports.is_empty());
+
+    // a separator reporting without consuming anything is not called again
+    fn stuck(input: CompleteStr) -> IResult<CompleteStr, Option<usize>> {
+      let (i, _) = sp(input)?;
+      if i.starts_with("?") {
+        Ok((i, Some(i.len())))
+      } else {
+        Ok((i, None))
+      }
+    }
+    fn stuck_parser<'a>(i: CompleteStr<'a>, reports: &mut Vec<usize>) -> IResult<CompleteStr<'a>, CompleteStr<'a>> {
+      ws_lenient!(i, stuck, reports, tag!("a"))
+    }
+    let mut reports = Vec::new();
+    assert_eq!(
+      stuck_parser(CompleteStr("? a"), &mut reports),
+      Err(Err::Error(error_position!(CompleteStr("? a"), ErrorKind::Tag)))
+    );
+    assert_eq!(reports, vec![3]);
+  }
+
+  #[test]
+  fn value() {
+    const TOKEN: u8 = 42;
+    named!(val<CompleteStr, u8>, ws!(value!(TOKEN, tuple!(tag!("a"), tag!("b")))));
+
+    assert_eq!(val(CompleteStr(" a b")), Ok((CompleteStr(""), TOKEN)));
+    assert_eq!(val(CompleteStr("a\tb c")), Ok((CompleteStr(unconsumed(" c")), TOKEN)));
+    assert_eq!(
+      val(CompleteStr(" a c")),
+      Err(Err::Error(nom_ws_error_position!(CompleteStr("c"), ErrorKind::Tag)))
+    );
+  }
+
+  #[test]
+  fn keyword_set() {
+    named!(modifiers<CompleteStr, [bool; 3]>,
+      ws!(keyword_set!(tag!("pub"), tag!("async"), tag!("unsafe")))
+    );
+
+    assert_eq!(modifiers(CompleteStr(" async pub")), Ok((CompleteStr(""), [true, true, false])));
+    assert_eq!(
+      modifiers(CompleteStr("unsafe\tpub fn")),
+      Ok((CompleteStr(unconsumed(" fn")), [true, false, true]))
+    );
+    assert_eq!(modifiers(CompleteStr(" fn")), Ok((CompleteStr("fn"), [false, false, false])));
+    assert_eq!(
+      modifiers(CompleteStr("pub pub")),
+      Ok((CompleteStr(unconsumed(" pub")), [true, false, false]))
+    );
+  }
+
+  #[test]
+  fn complete_byte_slice() {
+    assert_eq!(
+      sp(CompleteByteSlice(b" \t ")),
+      Ok((CompleteByteSlice(b""), CompleteByteSlice(b" \t ")))
+    );
+
+    named!(abc<CompleteByteSlice, CompleteByteSlice>, ws!(tag!("abc")));
+    assert_eq!(abc(CompleteByteSlice(b" abc")), Ok((CompleteByteSlice(b""), CompleteByteSlice(b"abc"))));
+    assert_eq!(abc(CompleteByteSlice(b"\tabc \n")), Ok((CompleteByteSlice(unconsumed_bytes(b" \n")), CompleteByteSlice(b"abc"))));
+
+    named!(tuple_2<CompleteByteSlice, (CompleteByteSlice, CompleteByteSlice)>,
+      ws!(tuple!(tag!("abc"), tag!("de")))
+    );
+    assert_eq!(
+      tuple_2(CompleteByteSlice(b" abc\tde ")),
+      Ok((CompleteByteSlice(unconsumed_bytes(b" ")), (CompleteByteSlice(b"abc"), CompleteByteSlice(b"de"))))
+    );
+    assert_eq!(
+      tuple_2(CompleteByteSlice(b" abc ")),
+      Err(Err::Error(nom_ws_error_position!(CompleteByteSlice(b""), ErrorKind::Eof)))
+    );
+
+    named!(alt_2<CompleteByteSlice, CompleteByteSlice>, ws!(alt!(tag!("abcd") | tag!("efgh"))));
+    assert_eq!(alt_2(CompleteByteSlice(b"  efgh")), Ok((CompleteByteSlice(b""), CompleteByteSlice(b"efgh"))));
+    assert_eq!(
+      alt_2(CompleteByteSlice(b" ")),
+      Err(Err::Error(nom_ws_error_position!(CompleteByteSlice(b""), ErrorKind::Alt)))
+    );
+  }
+
+  #[test]
+  fn opt() {
+    named!(digits<CompleteStr, Option<CompleteStr>>,
+      sep!(sp, opt!(take_while!(|c: char| c.is_ascii_digit())))
+    );
+
+    assert_eq!(digits(CompleteStr(" 12 abc")), Ok((CompleteStr(" abc"), Some(CompleteStr("12")))));
+    // zero width match: the separator is left for the caller
+    assert_eq!(digits(CompleteStr(" abc")), Ok((CompleteStr(" abc"), Some(CompleteStr("")))));
+
+    named!(abc<CompleteStr, Option<CompleteStr>>, sep!(sp, opt!(tag!("abc"))));
+    assert_eq!(abc(CompleteStr("  abc ")), Ok((CompleteStr(" "), Some(CompleteStr("abc")))));
+    assert_eq!(abc(CompleteStr("  def")), Ok((CompleteStr("  def"), None)));
+
+    named!(number<CompleteStr, (Option<CompleteStr>, CompleteStr)>,
+      ws!(tuple!(opt!(take_while!(|c: char| c.is_ascii_digit())), tag!("abc")))
+    );
+    assert_eq!(
+      number(CompleteStr(" 12 abc")),
+      Ok((CompleteStr(""), (Some(CompleteStr("12")), CompleteStr("abc"))))
+    );
+    assert_eq!(
+      number(CompleteStr(" abc")),
+      Ok((CompleteStr(""), (Some(CompleteStr("")), CompleteStr("abc"))))
+    );
+  }
+
+  named!(line_comment<CompleteStr, CompleteStr>, preceded!(tag!("//"), take_while!(|c| c != '\n')));
+  named!(block_comment<CompleteStr, CompleteStr>, delimited!(tag!("/*"), take_until!("*/"), tag!("*/")));
+  named!(comment_sp<CompleteStr, CompleteStr>, alt_sep_fn!(sp, line_comment, block_comment));
+
+  #[test]
+  fn alt_sep_fn() {
+    assert_eq!(comment_sp(CompleteStr("abc")), Ok((CompleteStr("abc"), CompleteStr(""))));
+    assert_eq!(
+      comment_sp(CompleteStr(" // a\n/* b */ \t/* c */abc")),
+      Ok((CompleteStr("abc"), CompleteStr(" // a\n/* b */ \t/* c */")))
+    );
+
+    named!(tuple_3<CompleteStr, (CompleteStr, CompleteStr, CompleteStr)>,
+      sep!(comment_sp, tuple!(tag!("a"), tag!("b"), tag!("c")))
+    );
+    assert_eq!(
+      tuple_3(CompleteStr("/* start */ a // first\n  b/**/c d")),
+      Ok((CompleteStr(" d"), (CompleteStr("a"), CompleteStr("b"), CompleteStr("c"))))
+    );
+  }
+
+  #[test]
+  fn tag_skip_comments() {
+    named!(key<CompleteStr, CompleteStr>, tag_skip_comments!("KEY", comment_sp));
+
+    assert_eq!(key(CompleteStr("  // note\n  KEY")), Ok((CompleteStr(""), CompleteStr("KEY"))));
+    assert_eq!(key(CompleteStr("/* a */KEY = 1")), Ok((CompleteStr(" = 1"), CompleteStr("KEY"))));
+    assert_eq!(
+      key(CompleteStr("  // note\n  KEYS")),
+      Err(Err::Error(error_position!(CompleteStr("KEYS"), ErrorKind::Tag)))
+    );
+    assert_eq!(
+      key(CompleteStr("  // note\n")),
+      Err(Err::Error(error_position!(CompleteStr(""), ErrorKind::Eof)))
+    );
+  }
+
+  #[cfg(feature = "alloc")]
+  #[test]
+  fn sep_ignoring() {
+    use nom::alpha;
+
+    named!(hash_comment<CompleteStr, CompleteStr>, preceded!(tag!("#"), take_while!(|c| c != '\n')));
+    named!(pragma<CompleteStr, (CompleteStr, CompleteStr)>, pair!(tag!("@pragma "), alpha));
+    named!(ignorable<CompleteStr, CompleteStr>, sep_ignoring!(sp, hash_comment, pragma));
+    named!(tokens<CompleteStr, Vec<CompleteStr> >, sep!(ignorable, many0!(alpha)));
+
+    assert_eq!(ignorable(CompleteStr("a")), Ok((CompleteStr("a"), CompleteStr(""))));
+    assert_eq!(
+      tokens(CompleteStr(" a # one\n@pragma once b\n  @pragma x# two\nc")),
+      Ok((CompleteStr(""), vec![CompleteStr("a"), CompleteStr("b"), CompleteStr("c")]))
+    );
+  }
+
+  #[cfg(feature = "alloc")]
+  #[test]
+  fn sep_trailing_comma() {
+    use nom::digit;
+    use super::{Separator, TrailingCommaSeparator};
+
+    fn array(i: CompleteStr) -> IResult<CompleteStr, Vec<CompleteStr>> {
+      let separator = TrailingCommaSeparator::new(sp, "[", "]");
+      sep_with!(i, &separator, delimited!(char!('['), separated_list!(char!(','), digit), char!(']')))
+    }
+
+    fn nested(i: CompleteStr) -> IResult<CompleteStr, Vec<Vec<CompleteStr>>> {
+      let separator = TrailingCommaSeparator::new(comment_sp, "[", "]");
+      sep_with!(i, &separator, delimited!(char!('['), separated_list!(char!(','),
+        delimited!(char!('['), separated_list!(char!(','), digit), char!(']'))
+      ), char!(']')))
+    }
+
+    assert_eq!(array(CompleteStr("[ 1, 2, ]")), Ok((CompleteStr(""), vec![CompleteStr("1"), CompleteStr("2")])));
+    assert_eq!(array(CompleteStr("[ 1, 2 ]")), Ok((CompleteStr(""), vec![CompleteStr("1"), CompleteStr("2")])));
+    assert_eq!(array(CompleteStr("[1 ,]")), Ok((CompleteStr(""), vec![CompleteStr("1")])));
+    assert_eq!(array(CompleteStr("[ ]")), Ok((CompleteStr(""), vec![])));
+    assert!(array(CompleteStr("[ 1, , ]")).is_err());
+    assert!(array(CompleteStr("[ , ]")).is_err());
+    assert!(array(CompleteStr("[,]")).is_err());
+
+    assert_eq!(
+      nested(CompleteStr("[[1, /* a */ ], [] , // b\n]")),
+      Ok((CompleteStr(""), vec![vec![CompleteStr("1")], vec![]]))
+    );
+    assert!(nested(CompleteStr("[[1], [ /* a */ , ]]")).is_err());
+
+    let separator = TrailingCommaSeparator::new(sp, "[", "]");
+    assert_eq!(separator.parse(CompleteStr(" , 3")), Ok((CompleteStr(", 3"), CompleteStr(" "))));
+    assert_eq!(separator.parse(CompleteStr(" , ]")), Ok((CompleteStr("]"), CompleteStr(" , "))));
+  }
+
+  #[test]
+  fn terminated_opt() {
+    named!(statement<CompleteStr, CompleteStr>, ws!(terminated_opt!(tag!("a"), tag!(";"))));
+
+    assert_eq!(statement(CompleteStr("a ;")), Ok((CompleteStr(""), CompleteStr("a"))));
+    assert_eq!(statement(CompleteStr(" a; a")), Ok((CompleteStr(unconsumed(" a")), CompleteStr("a"))));
+    assert_eq!(statement(CompleteStr("a")), Ok((CompleteStr(""), CompleteStr("a"))));
+    assert_eq!(statement(CompleteStr(" a \n")), Ok((CompleteStr(""), CompleteStr("a"))));
+    assert_eq!(
+      statement(CompleteStr("a b")),
+      Err(Err::Error(nom_ws_error_position!(CompleteStr("b"), ErrorKind::Tag)))
+    );
+  }
+
+  #[test]
+  fn switch_tuple() {
+    named!(sw<CompleteStr, CompleteStr>,
+      ws!(switch!(tuple!(tag!("a"), tag!("b")),
+        (CompleteStr("a"), CompleteStr("b")) => take!(2)
+      ))
+    );
+
+    assert_eq!(sw(CompleteStr(" a\tb  cd ef")), Ok((CompleteStr(unconsumed(" ef")), CompleteStr("cd"))));
+    assert_eq!(sw(CompleteStr("ab cd")), Ok((CompleteStr(""), CompleteStr("cd"))));
+    assert_eq!(
+      sw(CompleteStr(" a c")),
+      Err(Err::Error(nom_ws_error_node_position!(
+        CompleteStr("a c"),
+        ErrorKind::Switch,
+        nom_ws_error_position!(CompleteStr("c"), ErrorKind::Tag)
+      )))
+    );
+  }
+
+  named!(spaces<CompleteStr, CompleteStr>, eat_separator!(" \t"));
+
+  #[test]
+  fn ws_tok() {
+    named!(tuple_3<CompleteStr, (CompleteStr, CompleteStr, CompleteStr)>,
+      ws!(tuple!(tag!("a"), ws_tok!(spaces, tag!("b")), tag!("c")))
+    );
+
+    assert_eq!(
+      tuple_3(CompleteStr(" a \t b\n c")),
+      Ok((CompleteStr(""), (CompleteStr("a"), CompleteStr("b"), CompleteStr("c"))))
+    );
+    assert_eq!(
+      tuple_3(CompleteStr("a\nb c")),
+      Err(Err::Error(nom_ws_error_position!(CompleteStr("\nb c"), ErrorKind::Tag)))
+    );
+  }
+
+  #[cfg(feature = "alloc")]
+  #[test]
+  fn count_range() {
+    named!(two_three<CompleteStr, Vec<(CompleteStr, CompleteStr)> >,
+      ws!(count_range!(2, 3, pair!(tag!("a"), tag!("b"))))
+    );
+
+    assert_eq!(
+      two_three(CompleteStr(" ab a b")),
+      Ok((CompleteStr(""), vec![(CompleteStr("a"), CompleteStr("b")); 2]))
+    );
+    assert_eq!(
+      two_three(CompleteStr("ab ab\ta b  ab c")),
+      Ok((CompleteStr(unconsumed("  ab c")), vec![(CompleteStr("a"), CompleteStr("b")); 3]))
+    );
+    assert_eq!(
+      two_three(CompleteStr(" ab c")),
+      Err(Err::Error(nom_ws_error_position!(CompleteStr(" ab c"), ErrorKind::ManyMN)))
+    );
+
+    named!(many_m_n_2<CompleteStr, Vec<CompleteStr> >, ws!(many_m_n!(1, 2, tag!("a"))));
+    assert_eq!(
+      many_m_n_2(CompleteStr(" a a a")),
+      Ok((CompleteStr(unconsumed(" a")), vec![CompleteStr("a"); 2]))
+    );
+  }
+
+  #[cfg(feature = "alloc")]
+  #[test]
+  fn two_phase() {
+    use nom::{alpha, digit};
+    use super::{inline_sp, line_end};
+
+    named!(document<CompleteStr, ((CompleteStr, CompleteStr, CompleteStr), Vec<CompleteStr>)>,
+      two_phase!(
+        inline_sp, terminated!(tuple!(alpha, alpha, alpha), line_end),
+        sp, many1!(digit)
+      )
+    );
+
+    assert_eq!(
+      document(CompleteStr("data  v \t two\n 1 2\n\n  3\n\t4 ")),
+      Ok((CompleteStr(" "), (
+        (CompleteStr("data"), CompleteStr("v"), CompleteStr("two")),
+        vec![CompleteStr("1"), CompleteStr("2"), CompleteStr("3"), CompleteStr("4")]
+      )))
+    );
+    // the header separator does not cross lines
+    assert_eq!(
+      document(CompleteStr("data v\ntwo\n 1")),
+      Err(Err::Error(error_position!(CompleteStr("\ntwo\n 1"), ErrorKind::Alpha)))
+    );
+    // the body separator does
+    assert_eq!(
+      document(CompleteStr("data v two\n\n\n1")),
+      Ok((CompleteStr(""), ((CompleteStr("data"), CompleteStr("v"), CompleteStr("two")), vec![CompleteStr("1")])))
+    );
+  }
+
+  #[test]
+  fn ws_with() {
+    named!(tuple_2<CompleteStr, (CompleteStr, CompleteStr)>,
+      ws_with!(comment_sp, tuple!(tag!("a"), tag!("b")))
+    );
+
+    assert_eq!(
+      tuple_2(CompleteStr("a b // trailing")),
+      Ok((CompleteStr(""), (CompleteStr("a"), CompleteStr("b"))))
+    );
+    assert_eq!(
+      tuple_2(CompleteStr("/* a */ a /* b */ b /* c */ c")),
+      Ok((CompleteStr("c"), (CompleteStr("a"), CompleteStr("b"))))
+    );
+  }
+
+  macro_rules! comment_ws (
+    ($i:expr, $($args:tt)*) => (
+      ws_with!($i, comment_sp, $($args)*)
+    )
+  );
+
+  #[test]
+  fn custom_ws() {
+    named!(tuple_2<CompleteStr, (CompleteStr, CompleteStr)>,
+      comment_ws!(tuple!(tag!("a"), tag!("b")))
+    );
+
+    assert_eq!(
+      tuple_2(CompleteStr(" a /* b */ b /* end */ // really\n")),
+      Ok((CompleteStr(""), (CompleteStr("a"), CompleteStr("b"))))
+    );
+  }
+
+  #[cfg(feature = "alloc")]
+  #[test]
+  fn many1() {
+    named!(many1_a<CompleteStr, Vec<CompleteStr> >, ws!(many1!(tag!("a"))));
+
+    assert_eq!(many1_a(CompleteStr(" a\ta b")), Ok((CompleteStr(unconsumed(" b")), vec![CompleteStr("a"); 2])));
+    assert_eq!(
+      many1_a(CompleteStr("   b")),
+      Err(Err::Error(nom_ws_error_position!(CompleteStr("b"), ErrorKind::Many1)))
+    );
+  }
+
+  #[cfg(feature = "alloc")]
+  #[test]
+  fn separated_list_keep_sep() {
+    named!(sum<CompleteStr, (Vec<CompleteStr>, Vec<CompleteStr>)>,
+      ws!(separated_list_keep_sep!(alt!(tag!("+") | tag!("-")), take_while1!(|c: char| c.is_alphabetic())))
+    );
+
+    assert_eq!(
+      sum(CompleteStr(" a + b - c")),
+      Ok((
+        CompleteStr(""),
+        (
+          vec![CompleteStr("a"), CompleteStr("b"), CompleteStr("c")],
+          vec![CompleteStr("+"), CompleteStr("-")]
+        )
+      ))
+    );
+    assert_eq!(
+      sum(CompleteStr("a+b - 1")),
+      Ok((CompleteStr(unconsumed(" - 1")), (vec![CompleteStr("a"), CompleteStr("b")], vec![CompleteStr("+")])))
+    );
+    assert_eq!(sum(CompleteStr(" 1")), Ok((CompleteStr("1"), (vec![], vec![]))));
+  }
+
+  #[test]
+  fn tuple_optional() {
+    named!(tuple_3<CompleteStr, (CompleteStr, Option<CompleteStr>, CompleteStr)>,
+      ws!(tuple!(tag!("a"), tag!("b")?, tag!("c")))
+    );
+
+    assert_eq!(
+      tuple_3(CompleteStr(" a b\tc ")),
+      Ok((CompleteStr(unconsumed(" ")), (CompleteStr("a"), Some(CompleteStr("b")), CompleteStr("c"))))
+    );
+    assert_eq!(
+      tuple_3(CompleteStr(" a  c d")),
+      Ok((CompleteStr(unconsumed(" d")), (CompleteStr("a"), None, CompleteStr("c"))))
+    );
+
+    named!(b<CompleteStr, CompleteStr>, tag!("b"));
+    named!(tuple_2<CompleteStr, (CompleteStr, Option<CompleteStr>)>, ws!(tuple!(tag!("a"), b?)));
+    assert_eq!(tuple_2(CompleteStr(" a b")), Ok((CompleteStr(""), (CompleteStr("a"), Some(CompleteStr("b"))))));
+    assert_eq!(tuple_2(CompleteStr(" a c")), Ok((CompleteStr(unconsumed(" c")), (CompleteStr("a"), None))));
+  }
+
+  #[cfg(feature = "alloc")]
+  #[test]
+  fn trace_sep() {
+    use lib::std::cell::RefCell;
+
+    fn parser<'a>(
+      i: CompleteStr<'a>,
+      log: &RefCell<Vec<(CompleteStr<'a>, CompleteStr<'a>)>>,
+    ) -> IResult<CompleteStr<'a>, (CompleteStr<'a>, CompleteStr<'a>)> {
+      let traced = |input: CompleteStr<'a>| {
+        trace_sep!(input, sp, |i: &CompleteStr<'a>, res: &IResult<CompleteStr<'a>, CompleteStr<'a>>| {
+          if let Ok((_, consumed)) = *res {
+            log.borrow_mut().push((*i, consumed));
+          }
+        })
+      };
+      sep!(i, traced, tuple!(tag!("a"), tag!("b")))
+    }
+
+    let log = RefCell::new(Vec::new());
+    assert_eq!(
+      parser(CompleteStr("  a \t b c"), &log),
+      Ok((CompleteStr(" c"), (CompleteStr("a"), CompleteStr("b"))))
+    );
+    assert_eq!(
+      log.into_inner(),
+      vec![
+        (CompleteStr("  a \t b c"), CompleteStr("  ")),
+        (CompleteStr(" \t b c"), CompleteStr(" \t ")),
+      ]
+    );
+  }
+
+  #[cfg(feature = "debug")]
+  #[test]
+  fn trace_sep_print() {
+    named!(traced<CompleteStr, CompleteStr>, trace_sep!(sp));
+    named!(tuple_2<CompleteStr, (CompleteStr, CompleteStr)>, sep!(traced, tuple!(tag!("a"), tag!("b"))));
+
+    assert_eq!(tuple_2(CompleteStr(" a b")), Ok((CompleteStr(""), (CompleteStr("a"), CompleteStr("b")))));
+  }
+
+  #[test]
+  fn map_result() {
+    use nom::digit;
+
+    named!(byte<CompleteStr, u8>, ws!(map_result!(digit, |s: CompleteStr| s.0.parse::<u8>())));
+    assert_eq!(byte(CompleteStr("  12 ")), Ok((CompleteStr(unconsumed(" ")), 12)));
+    assert_eq!(
+      byte(CompleteStr("  300")),
+      Err(Err::Error(nom_ws_error_position!(CompleteStr("300"), ErrorKind::MapRes)))
+    );
+
+    named!(sum<CompleteStr, u8>,
+      ws!(map_result!(separated_pair!(digit, tag!("+"), digit), |(a, b): (CompleteStr, CompleteStr)| {
+        a.0.parse::<u8>().and_then(|a| b.0.parse::<u8>().map(|b| a.wrapping_add(b)))
+      }))
+    );
+    assert_eq!(sum(CompleteStr(" 1 + 2")), Ok((CompleteStr(""), 3)));
+    assert_eq!(
+      sum(CompleteStr(" 1 + 256")),
+      Err(Err::Error(nom_ws_error_position!(CompleteStr("1 + 256"), ErrorKind::MapRes)))
+    );
+  }
+
+  #[cfg(feature = "alloc")]
+  #[test]
+  fn separated_list_backtrack() {
+    named!(list<CompleteStr, Vec<CompleteStr> >, sep!(sp, separated_list!(tag!(","), tag!("a"))));
+    assert_eq!(list(CompleteStr(" a , ")), Ok((CompleteStr(" , "), vec![CompleteStr("a")])));
+    assert_eq!(list(CompleteStr(" a , a ,b")), Ok((CompleteStr(" ,b"), vec![CompleteStr("a"); 2])));
+
+    named!(list_ws<CompleteStr, Vec<CompleteStr> >, ws!(separated_list!(tag!(","), tag!("a"))));
+    assert_eq!(list_ws(CompleteStr(" a , ")), Ok((CompleteStr(unconsumed(" , ")), vec![CompleteStr("a")])));
+  }
+
+  #[cfg(feature = "alloc")]
+  #[test]
+  fn shell_token() {
+    use super::shell_token;
+
+    named!(command<CompleteStr, Vec<CompleteStr> >, ws!(many0!(shell_token)));
+    assert_eq!(
+      command(CompleteStr("  foo \"bar baz\" qux  ")),
+      Ok((CompleteStr(unconsumed("  ")), vec![CompleteStr("foo"), CompleteStr("bar baz"), CompleteStr("qux")]))
+    );
+    assert_eq!(
+      command(CompleteStr("\"\" a\"b")),
+      Ok((CompleteStr(""), vec![CompleteStr(""), CompleteStr("a\"b")]))
+    );
+
+    assert_eq!(
+      shell_token(CompleteStr("\"bar")),
+      Err(Err::Error(nom_ws_error_position!(CompleteStr("\"bar"), ErrorKind::Char)))
+    );
+    assert_eq!(shell_token(&b"\"bar"[..]), Err(Err::Incomplete(Needed::Unknown)));
+  }
+
+  #[test]
+  fn alt_success() {
+    // `alt!` consumes the separator before trying the branches
+    named!(abc_or_default<CompleteStr, CompleteStr>,
+      sep!(sp, alt!(tag!("abc") | success!(CompleteStr("default"))))
+    );
+    assert_eq!(abc_or_default(CompleteStr("  abc ")), Ok((CompleteStr(" "), CompleteStr("abc"))));
+    assert_eq!(abc_or_default(CompleteStr("  def")), Ok((CompleteStr("def"), CompleteStr("default"))));
+
+    // while with `alt_fallback!`, the fallback leaves it
+    named!(abc_or_fallback<CompleteStr, CompleteStr>,
+      sep!(sp, alt_fallback!(tag!("abc") | success!(CompleteStr("default"))))
+    );
+    assert_eq!(abc_or_fallback(CompleteStr("  abc ")), Ok((CompleteStr(" "), CompleteStr("abc"))));
+    assert_eq!(abc_or_fallback(CompleteStr("  def")), Ok((CompleteStr("  def"), CompleteStr("default"))));
+
+    named!(tuple_2<CompleteStr, (CompleteStr, CompleteStr)>,
+      ws!(tuple!(alt_fallback!(tag!("abc") | success!(CompleteStr("default"))), tag!("def")))
+    );
+    assert_eq!(
+      tuple_2(CompleteStr("  def")),
+      Ok((CompleteStr(""), (CompleteStr("default"), CompleteStr("def"))))
+    );
+  }
+
+  #[cfg(feature = "alloc")]
+  #[test]
+  fn kv_list() {
+    use nom::{alpha, digit};
+
+    named!(tags<CompleteStr, Vec<(CompleteStr, CompleteStr)> >, ws!(kv_list!(sp, tag!("="), alpha, digit)));
+    assert_eq!(
+      tags(CompleteStr("  a = 1   b=2 ")),
+      Ok((CompleteStr(unconsumed(" ")), vec![(CompleteStr("a"), CompleteStr("1")), (CompleteStr("b"), CompleteStr("2"))]))
+    );
+    assert_eq!(tags(CompleteStr("  1=a")), Ok((CompleteStr("1=a"), vec![])));
+
+    named!(query<CompleteStr, Vec<(CompleteStr, CompleteStr)> >,
+      ws!(kv_list!(tag!("&"), tag!("="), alpha, alpha))
+    );
+    assert_eq!(
+      query(CompleteStr("a=b & c = d &")),
+      Ok((CompleteStr(unconsumed(" &")), vec![(CompleteStr("a"), CompleteStr("b")), (CompleteStr("c"), CompleteStr("d"))]))
+    );
+  }
+
+  #[test]
+  fn sep_bounded() {
+    use std::cell::Cell;
+    use nom::InputLength;
+    use super::SEP_BOUNDED_ERROR;
+
+    named!(space<CompleteStr, CompleteStr>, sep_bounded!(8, sp));
+    named!(list<CompleteStr, Vec<CompleteStr> >, sep!(space, many0!(tag!("a"))));
+
+    assert_eq!(
+      list(CompleteStr(" a\n\ta        a")),
+      Ok((CompleteStr(""), vec![CompleteStr("a"), CompleteStr("a"), CompleteStr("a")]))
+    );
+
+    let spaces = " ".repeat(4096);
+    let input = format!("a{}a", spaces);
+    assert_eq!(
+      list(CompleteStr(&input)),
+      Err(Err::Failure(error_position!(CompleteStr(&input[1..]), ErrorKind::Custom(SEP_BOUNDED_ERROR))))
+    );
+
+    // the separator never sees more than `max + 1` elements
+    let long = format!("a{}a", " ".repeat(1 << 20));
+    let longest = Cell::new(0);
+    let counting_sp = |i| {
+      longest.set(longest.get().max(InputLength::input_len(&i)));
+      sp(i)
+    };
+    assert_eq!(
+      sep_bounded!(CompleteStr(&long[1..]), 8, counting_sp),
+      Err(Err::Failure(error_position!(CompleteStr(&long[1..]), ErrorKind::Custom(SEP_BOUNDED_ERROR))))
+    );
+    assert_eq!(longest.get(), 9);
+
+    // nor on partial input, where `sp` would ask for more
+    named!(space_bytes<&[u8], &[u8]>, sep_bounded!(8, sp));
+    assert_eq!(space_bytes(&b"  a"[..]), Ok((&b"a"[..], &b"  "[..])));
+    assert_eq!(
+      space_bytes(&[b' '; 20][..]),
+      Err(Err::Failure(error_position!(&[b' '; 20][..], ErrorKind::Custom(SEP_BOUNDED_ERROR))))
+    );
+  }
+
+  #[test]
+  fn head_then_sep() {
+    use nom::alpha;
+
+    named!(words<CompleteStr, (CompleteStr, Vec<CompleteStr>)>, ws!(head_then_sep!(alpha, many0!(alpha))));
+    assert_eq!(
+      words(CompleteStr("a b c")),
+      Ok((CompleteStr(""), (CompleteStr("a"), vec![CompleteStr("b"), CompleteStr("c")])))
+    );
+    assert_eq!(
+      words(CompleteStr(" a b c")),
+      Err(Err::Error(error_position!(CompleteStr(" a b c"), ErrorKind::Alpha)))
+    );
+  }
+
+  #[test]
+  fn length_data() {
+    use nom::digit;
+
+    named!(number<CompleteStr, usize>, map_res!(digit, |d: CompleteStr| d.parse::<usize>()));
+    named!(data<CompleteStr, CompleteStr>, ws!(length_data!(number)));
+
+    assert_eq!(data(CompleteStr("  3 abc")), Ok((CompleteStr(""), CompleteStr("abc"))));
+    assert_eq!(data(CompleteStr("3 a c d")), Ok((CompleteStr(unconsumed(" d")), CompleteStr("a c"))));
+    assert_eq!(data(CompleteStr("3  a  ")), Ok((CompleteStr(""), CompleteStr("a  "))));
+
+    // the data can also start right after the count
+    named!(adjacent<CompleteStr, CompleteStr>, ws!(length_data_no_sep!(number)));
+    assert_eq!(adjacent(CompleteStr("  3abc")), Ok((CompleteStr(""), CompleteStr("abc"))));
+    assert_eq!(adjacent(CompleteStr("  3 abc")), Ok((CompleteStr("c"), CompleteStr(" ab"))));
+    assert_eq!(adjacent(CompleteStr("4  a  ")), Ok((CompleteStr(unconsumed(" ")), CompleteStr("  a "))));
+  }
+
+  #[test]
+  fn paragraph_break() {
+    use nom::alpha;
+
+    named!(two_words<CompleteStr, (CompleteStr, CompleteStr, CompleteStr)>,
+      ws!(tuple!(alpha, paragraph_break!(), alpha))
+    );
+    assert_eq!(
+      two_words(CompleteStr("a\n\nb")),
+      Ok((CompleteStr(""), (CompleteStr("a"), CompleteStr("\n\n"), CompleteStr("b"))))
+    );
+    assert_eq!(
+      two_words(CompleteStr("a \r\n \t\r\n b")),
+      Ok((CompleteStr(""), (CompleteStr("a"), CompleteStr(" \r\n \t\r\n "), CompleteStr("b"))))
+    );
+    assert_eq!(
+      two_words(CompleteStr("a\nb")),
+      Err(Err::Error(error_position!(CompleteStr("\nb"), ErrorKind::MultiSpace)))
+    );
+
+    named!(has_break<CompleteStr, bool>, preceded!(alpha, map!(opt!(paragraph_break!()), |b| b.is_some())));
+    assert_eq!(has_break(CompleteStr("a\nb")), Ok((CompleteStr("\nb"), false)));
+    assert_eq!(has_break(CompleteStr("a\n\nb")), Ok((CompleteStr("b"), true)));
+    assert_eq!(has_break(CompleteStr("a\n\n")), Ok((CompleteStr(""), true)));
+    named!(streaming_break, paragraph_break!());
+    assert_eq!(streaming_break(&b"\n\n"[..]), Err(Err::Incomplete(Needed::Size(1))));
+  }
+
+  #[test]
+  fn verify_input() {
+    use nom::digit;
+
+    named!(number<CompleteStr, u32>,
+      ws!(verify_input!(
+        map_res!(digit, |d: CompleteStr| d.parse::<u32>()),
+        |n: &u32, consumed: &CompleteStr| *n == 0 || !consumed.starts_with("0")
+      ))
+    );
+
+    assert_eq!(number(CompleteStr("  7")), Ok((CompleteStr(""), 7)));
+    assert_eq!(number(CompleteStr("  0 ")), Ok((CompleteStr(unconsumed(" ")), 0)));
+    assert_eq!(
+      number(CompleteStr("  007")),
+      Err(Err::Error(error_position!(CompleteStr("007"), ErrorKind::Verify)))
+    );
+  }
+
+  #[test]
+  fn verify_map() {
+    use nom::digit;
+
+    named!(number<CompleteStr, u32>, map_res!(digit, |d: CompleteStr| d.parse::<u32>()));
+    named!(small_double<CompleteStr, u32>,
+      ws!(verify!(map!(number, |n: u32| n * 2), |n: &u32| *n < 100))
+    );
+
+    assert_eq!(small_double(CompleteStr("  40")), Ok((CompleteStr(""), 80)));
+    assert_eq!(
+      small_double(CompleteStr("  60")),
+      Err(Err::Error(error_position!(CompleteStr("60"), ErrorKind::Verify)))
+    );
+
+    named!(pair_double<CompleteStr, (u32, u32)>,
+      ws!(verify!(map!(pair!(number, number), |(a, b)| (a * 2, b * 2)), |&(a, b): &(u32, u32)| a < b))
+    );
+    assert_eq!(pair_double(CompleteStr(" 1  2 ")), Ok((CompleteStr(unconsumed(" ")), (2, 4))));
+  }
+
+  #[test]
+  fn separated_pair_alt() {
+    use nom::alpha;
+
+    named!(binding<CompleteStr, (CompleteStr, CompleteStr, CompleteStr)>,
+      ws!(separated_pair_alt!(alpha, [tag!("=>"), tag!("="), tag!(":")], alpha))
+    );
+
+    assert_eq!(binding(CompleteStr(" a : b ")), Ok((CompleteStr(unconsumed(" ")), (CompleteStr("a"), CompleteStr(":"), CompleteStr("b")))));
+    assert_eq!(binding(CompleteStr(" a=b ")), Ok((CompleteStr(unconsumed(" ")), (CompleteStr("a"), CompleteStr("="), CompleteStr("b")))));
+    assert_eq!(binding(CompleteStr(" a => b ")), Ok((CompleteStr(unconsumed(" ")), (CompleteStr("a"), CompleteStr("=>"), CompleteStr("b")))));
+    assert_eq!(
+      binding(CompleteStr(" a - b ")),
+      Err(Err::Error(error_position!(CompleteStr("- b "), ErrorKind::Alt)))
+    );
+  }
+
+  #[test]
+  fn map_reject() {
+    use nom::digit;
+
+    fn level(d: CompleteStr) -> i32 {
+      d.parse().ok().filter(|n| *n <= 9).unwrap_or(-1)
+    }
+
+    named!(levels<CompleteStr, (i32, i32)>,
+      ws!(pair!(map_reject!(digit, level, |n: &i32| *n < 0), map_reject!(digit, level, |n: &i32| *n < 0)))
+    );
+
+    assert_eq!(levels(CompleteStr(" 3 9 ")), Ok((CompleteStr(unconsumed(" ")), (3, 9))));
+    assert_eq!(
+      levels(CompleteStr(" 3  12 ")),
+      Err(Err::Error(error_position!(CompleteStr("12 "), ErrorKind::MapOpt)))
+    );
+  }
+
+  #[cfg(feature = "alloc")]
+  #[test]
+  fn verify_many0() {
+    named!(number<CompleteStr, u32>, map_res!(nom::digit, |d: CompleteStr| d.parse::<u32>()));
+    named!(two_even<CompleteStr, Vec<u32> >,
+      ws!(verify!(many0!(number), |v: &Vec<u32>| v.iter().filter(|n| *n % 2 == 0).count() >= 2))
+    );
+
+    assert_eq!(two_even(CompleteStr(" 2 3 4")), Ok((CompleteStr(""), vec![2, 3, 4])));
+    assert_eq!(
+      two_even(CompleteStr(" 1 2 3")),
+      Err(Err::Error(error_position!(CompleteStr("1 2 3"), ErrorKind::Verify)))
+    );
+  }
+
+  #[test]
+  fn verify_once() {
+    use nom::digit;
+    use std::cell::Cell;
+
+    fn counted<'a>(i: CompleteStr<'a>, calls: &Cell<usize>) -> IResult<CompleteStr<'a>, CompleteStr<'a>> {
+      calls.set(calls.get() + 1);
+      digit(i)
+    }
+
+    fn short<'a>(i: CompleteStr<'a>, calls: &Cell<usize>) -> IResult<CompleteStr<'a>, CompleteStr<'a>> {
+      ws!(i, verify!(call!(counted, calls), |d: &CompleteStr| d.len() < 3))
+    }
+
+    let calls = Cell::new(0);
+    assert_eq!(short(CompleteStr(" 12 "), &calls), Ok((CompleteStr(unconsumed(" ")), CompleteStr("12"))));
+    assert_eq!(calls.get(), 1);
+
+    let calls = Cell::new(0);
+    assert_eq!(
+      short(CompleteStr(" 1234 "), &calls),
+      Err(Err::Error(error_position!(CompleteStr("1234 "), ErrorKind::Verify)))
+    );
+    assert_eq!(calls.get(), 1);
+  }
+
+  #[test]
+  fn escaped_flagged() {
+    use nom::alpha;
+
+    named!(string<CompleteStr, (CompleteStr, bool)>,
+      ws!(escaped_flagged!(alpha, '\\', one_of!("\"n\\")))
+    );
+    assert_eq!(string(CompleteStr("  abc")), Ok((CompleteStr(""), (CompleteStr("abc"), false))));
+    assert_eq!(string(CompleteStr("  a\\nb ")), Ok((CompleteStr(unconsumed(" ")), (CompleteStr("a\\nb"), true))));
+  }
+
+  #[test]
+  fn ws_recognize() {
+    named!(span<CompleteStr, CompleteStr>, ws_recognize!(tuple!(tag!("a"), tag!("b"))));
+    assert_eq!(span(CompleteStr("  a b  ")), Ok((CompleteStr(""), CompleteStr("a b"))));
+    assert_eq!(span(CompleteStr("a\n\tb;")), Ok((CompleteStr(";"), CompleteStr("a\n\tb"))));
+    assert_eq!(
+      span(CompleteStr("  a c")),
+      Err(Err::Error(error_position!(CompleteStr("c"), ErrorKind::Tag)))
+    );
+  }
+
+  #[test]
+  fn not_peek() {
+    use nom::alpha;
+
+    named!(not_end<CompleteStr, ()>, sep!(sp, not!(peek!(tag!("end")))));
+    assert_eq!(not_end(CompleteStr("  value")), Ok((CompleteStr("  value"), ())));
+    assert_eq!(
+      not_end(CompleteStr("  end")),
+      Err(Err::Error(error_position!(CompleteStr("end"), ErrorKind::Not)))
+    );
+
+    // ws! still consumes the whitespace after the (empty) match
+    named!(ws_not_end<CompleteStr, ()>, ws!(not!(peek!(tag!("end")))));
+    assert_eq!(ws_not_end(CompleteStr("  value")), Ok((CompleteStr(unconsumed("  value")), ())));
+
+    named!(value<CompleteStr, CompleteStr>, ws!(preceded!(not!(peek!(tag!("end"))), alpha)));
+    assert_eq!(value(CompleteStr("  value ")), Ok((CompleteStr(unconsumed(" ")), CompleteStr("value"))));
+    assert!(value(CompleteStr("  end")).is_err());
+  }
+
+  #[test]
+  fn eol_terminated() {
+    use nom::alpha;
+
+    named!(line<CompleteStr, CompleteStr>, eol_terminated!(alpha));
+    assert_eq!(line(CompleteStr("a\n")), Ok((CompleteStr(""), CompleteStr("a"))));
+    assert_eq!(line(CompleteStr("a\r\n")), Ok((CompleteStr(""), CompleteStr("a"))));
+    assert_eq!(line(CompleteStr("a")), Ok((CompleteStr(""), CompleteStr("a"))));
+    assert_eq!(line(CompleteStr(" \ta \t\nb")), Ok((CompleteStr("b"), CompleteStr("a"))));
+    assert_eq!(
+      line(CompleteStr("a b")),
+      Err(Err::Error(error_position!(CompleteStr("b"), ErrorKind::CrLf)))
+    );
+    assert_eq!(
+      line(CompleteStr("\na")),
+      Err(Err::Error(error_position!(CompleteStr("\na"), ErrorKind::Alpha)))
+    );
+
+    named!(streaming_line<&str, &str>, eol_terminated!(tag!("a")));
+    assert_eq!(streaming_line("a\n"), Ok(("", "a")));
+    assert_eq!(streaming_line("a"), Err(Err::Incomplete(Needed::Size(1))));
+  }
+
+  #[test]
+  fn complete() {
+    named!(streaming<Vec<&[u8]> >, sep!(sp, many0!(tag!("a"))));
+    named!(list<Vec<&[u8]> >, sep!(sp, complete!(many0!(tag!("a")))));
+
+    assert_eq!(streaming(&b" a a"[..]), Err(Err::Incomplete(Needed::Size(1))));
+    assert_eq!(
+      list(&b" a a"[..]),
+      Err(Err::Error(error_position!(&b" a a"[..], ErrorKind::Complete)))
+    );
+    assert_eq!(list(&b" a aX"[..]), Ok((&b"X"[..], vec![&b"a"[..], &b"a"[..]])));
+  }
+
+  // neither Copy nor Clone, to check that the _sep macros do not need it
+  #[cfg(feature = "alloc")]
+  #[derive(Debug, PartialEq)]
+  struct Word(String);
+
+  #[cfg(feature = "alloc")]
+  named!(word<CompleteStr, Word>, map!(::nom::alpha, |s: CompleteStr| Word(s.to_string())));
+
+  #[cfg(feature = "alloc")]
+  #[test]
+  fn owned_outputs() {
+    named!(words<CompleteStr, Vec<Word> >, ws!(many0!(word)));
+    assert_eq!(
+      words(CompleteStr(" a bc ")),
+      Ok((CompleteStr(unconsumed(" ")), vec![Word("a".to_string()), Word("bc".to_string())]))
+    );
+
+    named!(list<CompleteStr, Vec<Word> >, ws!(separated_list!(tag!(","), word)));
+    assert_eq!(
+      list(CompleteStr(" a , bc ")),
+      Ok((CompleteStr(unconsumed(" ")), vec![Word("a".to_string()), Word("bc".to_string())]))
+    );
+
+    named!(pair<CompleteStr, (Word, Option<Word>)>, ws!(tuple!(word, word?)));
+    assert_eq!(
+      pair(CompleteStr(" a bc ")),
+      Ok((CompleteStr(unconsumed(" ")), (Word("a".to_string()), Some(Word("bc".to_string())))))
+    );
+
+    named!(sentence<CompleteStr, String>,
+      fold_many0!(ws!(word), String::new(), |mut acc: String, w: Word| {
+        acc.push_str(&w.0);
+        acc
+      })
+    );
+    assert_eq!(sentence(CompleteStr(" a bc d")), Ok((CompleteStr(""), "abcd".to_string())));
+  }
+
+  #[test]
+  fn pair_ws_count() {
+    named!(pair<CompleteStr, (CompleteStr, CompleteStr, usize)>, ws!(pair_ws_count!(tag!("a"), tag!("b"))));
+    assert_eq!(pair(CompleteStr("a   b")), Ok((CompleteStr(""), (CompleteStr("a"), CompleteStr("b"), 3))));
+    assert_eq!(pair(CompleteStr(" ab ")), Ok((CompleteStr(unconsumed(" ")), (CompleteStr("a"), CompleteStr("b"), 0))));
+    assert_eq!(pair(CompleteStr("a\t\nb")), Ok((CompleteStr(""), (CompleteStr("a"), CompleteStr("b"), 2))));
+  }
+
+  #[test]
+  fn map_opt() {
+    use nom::{alpha, digit};
+
+    fn keyword(s: CompleteStr) -> Option<u8> {
+      match s.0 {
+        "valid" => Some(1),
+        _       => None,
+      }
+    }
+
+    named!(kw<CompleteStr, u8>, ws!(map_opt!(alpha, keyword)));
+    assert_eq!(kw(CompleteStr("  valid ")), Ok((CompleteStr(unconsumed(" ")), 1)));
+    assert_eq!(
+      kw(CompleteStr("  notvalid")),
+      Err(Err::Error(error_position!(CompleteStr("notvalid"), ErrorKind::MapOpt)))
+    );
+
+    named!(pair<CompleteStr, (u8, u8)>, ws!(map_opt!(pair!(digit, digit), |(a, b): (CompleteStr, CompleteStr)| {
+      Some((a.parse::<u8>().ok()?, b.parse::<u8>().ok()?))
+    })));
+    assert_eq!(pair(CompleteStr(" 1 2")), Ok((CompleteStr(""), (1, 2))));
+    assert_eq!(
+      pair(CompleteStr(" 1 999")),
+      Err(Err::Error(error_position!(CompleteStr("1 999"), ErrorKind::MapOpt)))
+    );
+  }
+
+  #[cfg(feature = "std")]
+  #[test]
+  fn separated_set() {
+    use nom::alpha;
+    use std::collections::HashSet;
+
+    named!(tags<CompleteStr, HashSet<CompleteStr> >, ws!(separated_set!(sp, alpha)));
+    let expected: HashSet<CompleteStr> = vec![CompleteStr("a"), CompleteStr("b"), CompleteStr("c")].into_iter().collect();
+    assert_eq!(tags(CompleteStr(" a b a c")), Ok((CompleteStr(""), expected)));
+
+    named!(list<CompleteStr, HashSet<CompleteStr> >, ws!(separated_set!(tag!(","), alpha)));
+    let expected: HashSet<CompleteStr> = vec![CompleteStr("a"), CompleteStr("b")].into_iter().collect();
+    assert_eq!(list(CompleteStr("b, a ,b")), Ok((CompleteStr(""), expected)));
+  }
+
+  #[cfg(feature = "alloc")]
+  #[test]
+  fn separated_list_empty_elements() {
+    use nom::alpha;
+
+    named!(fields<CompleteStr, Vec<Option<CompleteStr>> >, ws!(separated_list!(char!(','), opt!(alpha))));
+    assert_eq!(
+      fields(CompleteStr(" a , , c")),
+      Ok((CompleteStr(""), vec![Some(CompleteStr("a")), None, Some(CompleteStr("c"))]))
+    );
+    assert_eq!(
+      fields(CompleteStr("a,,c")),
+      Ok((CompleteStr(""), vec![Some(CompleteStr("a")), None, Some(CompleteStr("c"))]))
+    );
+    assert_eq!(
+      fields(CompleteStr(" , a,")),
+      Ok((CompleteStr(""), vec![None, Some(CompleteStr("a")), None]))
+    );
+    assert_eq!(
+      fields(CompleteStr(" ; ")),
+      Err(Err::Error(error_position!(CompleteStr("; "), ErrorKind::SeparatedList)))
+    );
+
+    // zero width elements and separators cannot loop
+    named!(empty<CompleteStr, Vec<Option<CompleteStr>> >, ws!(separated_list!(opt!(char!(',')), opt!(alpha))));
+    assert_eq!(
+      empty(CompleteStr("a,,b,")),
+      Ok((CompleteStr(""), vec![Some(CompleteStr("a")), None, Some(CompleteStr("b")), None]))
+    );
+    assert_eq!(empty(CompleteStr("a b")), Ok((CompleteStr(unconsumed(" b")), vec![Some(CompleteStr("a"))])));
+  }
+
+  #[test]
+  fn tag_bounded() {
+    named!(kw<CompleteStr, CompleteStr>, ws!(tag_bounded!("if")));
+    assert_eq!(kw(CompleteStr(" if")), Ok((CompleteStr(""), CompleteStr("if"))));
+    assert_eq!(kw(CompleteStr(" if(a)")), Ok((CompleteStr("(a)"), CompleteStr("if"))));
+    assert_eq!(
+      kw(CompleteStr(" iffy")),
+      Err(Err::Error(error_position!(CompleteStr("iffy"), ErrorKind::Tag)))
+    );
+
+    named!(paren<CompleteStr, (char, CompleteStr)>, ws!(pair!(char!('('), tag_bounded!("if"))));
+    assert_eq!(paren(CompleteStr("(if x")), Ok((CompleteStr(unconsumed(" x")), ('(', CompleteStr("if")))));
+    assert_eq!(paren(CompleteStr(" ( if")), Ok((CompleteStr(""), ('(', CompleteStr("if")))));
+    assert!(paren(CompleteStr("(if_x")).is_err());
+
+    named!(dash<CompleteStr, CompleteStr>, ws!(tag_bounded!("if", |c: char| c.is_alphanumeric() || c == '-')));
+    assert!(dash(CompleteStr("if-x")).is_err());
+    assert_eq!(dash(CompleteStr("if_x")), Ok((CompleteStr("_x"), CompleteStr("if"))));
+
+    named!(streaming<&str, &str>, tag_bounded!("if"));
+    assert_eq!(streaming("if"), Err(Err::Incomplete(Needed::Unknown)));
+  }
+
+  #[test]
+  fn terminator() {
+    named!(end<CompleteStr, CompleteStr>, terminator!());
+    assert_eq!(end(CompleteStr("   \n")), Ok((CompleteStr(""), CompleteStr("\n"))));
+    assert_eq!(end(CompleteStr("\r\n\n")), Ok((CompleteStr("\n"), CompleteStr("\r\n"))));
+    assert_eq!(end(CompleteStr(" \t")), Ok((CompleteStr(""), CompleteStr(""))));
+    assert_eq!(
+      end(CompleteStr("  a\n")),
+      Err(Err::Error(error_position!(CompleteStr("a\n"), ErrorKind::CrLf)))
+    );
+
+    named!(streaming_end<&str, &str>, terminator!());
+    assert_eq!(streaming_end("  "), Err(Err::Incomplete(Needed::Size(1))));
+    assert_eq!(streaming_end("\r"), Err(Err::Incomplete(Needed::Size(2))));
+  }
+
+  #[test]
+  fn many0_acc() {
+    // fixed capacity container, dropping the oldest element
+    #[derive(Debug, PartialEq)]
+    struct Ring {
+      items: [u8; 2],
+      len:   usize,
+    }
+
+    impl Ring {
+      fn push(mut self, item: u8) -> Ring {
+        self.items[self.len % 2] = item;
+        self.len += 1;
+        self
+      }
+    }
+
+    named!(ring<CompleteStr, Ring>,
+      ws!(many0_acc!(
+        tuple!(tag!("("), take!(1), tag!(")")),
+        Ring { items: [0; 2], len: 0 },
+        |acc: Ring, (_, c, _): (CompleteStr, CompleteStr, CompleteStr)| acc.push(c.as_bytes()[0])
+      ))
+    );
+    assert_eq!(
+      ring(CompleteStr(" ( a ) (b)( c ) ")),
+      Ok((CompleteStr(unconsumed(" ")), Ring { items: [b'c', b'b'], len: 3 }))
+    );
+
+    named!(count<CompleteStr, usize>, ws!(fold_many0!(tag!("a"), 0, |acc, _| acc + 1)));
+    assert_eq!(count(CompleteStr(" a a  a b")), Ok((CompleteStr(unconsumed(" b")), 3)));
+  }
+
+  #[test]
+  fn do_parse_custom_error() {
+    use nom::{alpha, digit, Convert};
+
+    #[derive(Debug, PartialEq)]
+    enum AssignError {
+      Code(u32),
+      Number,
+    }
+
+    impl From<u32> for AssignError {
+      fn from(code: u32) -> AssignError {
+        AssignError::Code(code)
+      }
+    }
+
+    fn number(i: CompleteStr) -> IResult<CompleteStr, u8, AssignError> {
+      let (i1, d) = digit(i).map_err(Err::convert)?;
+      match d.parse::<u8>() {
+        Ok(n)  => Ok((i1, n)),
+        Err(_) => Err(Err::Error(error_position!(i, ErrorKind::Custom(AssignError::Number)))),
+      }
+    }
+
+    named!(assign<CompleteStr, (CompleteStr, u8), AssignError>,
+      ws!(do_parse!(
+        name: alpha >>
+        tag!("=") >>
+        value: number >>
+        (name, value)
+      ))
+    );
+
+    assert_eq!(assign(CompleteStr(" a =  12 ")), Ok((CompleteStr(unconsumed(" ")), (CompleteStr("a"), 12))));
+    assert_eq!(
+      assign(CompleteStr(" a = 1234")),
+      Err(Err::Error(error_position!(CompleteStr("1234"), ErrorKind::Custom(AssignError::Number))))
+    );
+    assert_eq!(
+      assign(CompleteStr(" a : 1")),
+      Err(Err::Error(error_position!(CompleteStr(": 1"), ErrorKind::Tag)))
+    );
+  }
+
+  #[cfg(feature = "alloc")]
+  #[test]
+  fn columns() {
+    use super::inline_sp;
+
+    named!(process<CompleteStr, Vec<CompleteStr> >, sep!(inline_sp, columns!(3)));
+    assert_eq!(
+      process(CompleteStr("proc   123   /bin/my app")),
+      Ok((CompleteStr(""), vec![CompleteStr("proc"), CompleteStr("123"), CompleteStr("/bin/my app")]))
+    );
+    assert_eq!(
+      process(CompleteStr(" proc\t123 /bin/my  app  \nnext")),
+      Ok((CompleteStr("  \nnext"), vec![CompleteStr("proc"), CompleteStr("123"), CompleteStr("/bin/my  app")]))
+    );
+    assert_eq!(
+      process(CompleteStr("proc 123")),
+      Err(Err::Error(error_position!(CompleteStr("123"), ErrorKind::Count)))
+    );
+    assert_eq!(
+      process(CompleteStr("proc 123 \n")),
+      Err(Err::Error(error_position!(CompleteStr("\n"), ErrorKind::Count)))
+    );
+
+    // a column cannot span several lines
+    named!(pair<CompleteStr, Vec<CompleteStr> >, sep!(inline_sp, columns!(2)));
+    assert_eq!(
+      pair(CompleteStr("ab\ncd ef")),
+      Err(Err::Error(error_position!(CompleteStr("\ncd ef"), ErrorKind::Count)))
+    );
+    assert_eq!(
+      pair(CompleteStr("ab\r\ncd ef")),
+      Err(Err::Error(error_position!(CompleteStr("\r\ncd ef"), ErrorKind::Count)))
+    );
+
+    named!(lines<CompleteStr, Vec<Vec<CompleteStr> > >, many0!(eol_terminated!(columns!(2))));
+    assert_eq!(
+      lines(CompleteStr("a b c\nd  e\n")),
+      Ok((CompleteStr(""), vec![
+        vec![CompleteStr("a"), CompleteStr("b c")],
+        vec![CompleteStr("d"), CompleteStr("e")],
+      ]))
+    );
+  }
+
+  #[test]
+  fn no_ws_match() {
+    named!(list<CompleteStr, Vec<CompleteStr> >, ws!(many1!(tag!("a"))));
+    named!(pair<CompleteStr, (CompleteStr, CompleteStr)>, ws!(pair!(tag!("a"), tag!("b"))));
+    assert_no_ws_match!(list, CompleteStr("   "));
+    assert_no_ws_match!(pair, CompleteStr(" \r\n\t"));
+    assert_no_ws_match!(pair, CompleteStr(""));
+  }
+
+  #[test]
+  #[should_panic(expected = "matched the whitespace only input")]
+  fn no_ws_match_empty_match() {
+    named!(list<CompleteStr, Vec<CompleteStr> >, ws!(many0!(tag!("a"))));
+    assert_no_ws_match!(list, CompleteStr("  \n "));
+  }
+
+  #[test]
+  #[should_panic(expected = "is not whitespace only")]
+  fn no_ws_match_not_whitespace() {
+    named!(list<CompleteStr, Vec<CompleteStr> >, ws!(many1!(tag!("a"))));
+    assert_no_ws_match!(list, CompleteStr(" b "));
+  }
+
+  #[test]
+  fn sp_crlf_aware() {
+    use super::sp_crlf_aware;
+
+    assert_eq!(sp_crlf_aware(CompleteStr("\r\na")), Ok((CompleteStr("a"), 1)));
+    assert_eq!(sp_crlf_aware(CompleteStr("\ra")), Ok((CompleteStr("a"), 1)));
+    assert_eq!(sp_crlf_aware(CompleteStr("\na")), Ok((CompleteStr("a"), 1)));
+    assert_eq!(sp_crlf_aware(CompleteStr("\n\ra")), Ok((CompleteStr("a"), 2)));
+    assert_eq!(sp_crlf_aware(CompleteStr(" \r\n \r\n\r\r\n\n")), Ok((CompleteStr(""), 5)));
+    assert_eq!(sp_crlf_aware(CompleteStr(" \t a")), Ok((CompleteStr("a"), 0)));
+
+    named!(pair<CompleteStr, (CompleteStr, CompleteStr)>,
+      sep!(sp_crlf_aware, pair!(tag!("a"), tag!("b")))
+    );
+    assert_eq!(pair(CompleteStr("a\r\nb")), Ok((CompleteStr(""), (CompleteStr("a"), CompleteStr("b")))));
+  }
+
+  #[test]
+  fn opt_alt() {
+    named!(choice<CompleteStr, Option<CompleteStr> >, sep!(sp, opt!(alt!(tag!("x") | tag!("y")))));
+
+    assert_eq!(choice(CompleteStr(" b")), Ok((CompleteStr(" b"), None)));
+    assert_eq!(choice(CompleteStr(" y b")), Ok((CompleteStr(" b"), Some(CompleteStr("y")))));
+
+    named!(prefixed<CompleteStr, (Option<CompleteStr>, CompleteStr)>,
+      ws!(pair!(opt!(alt!(tag!("x") | tag!("y"))), tag!("b")))
+    );
+    assert_eq!(prefixed(CompleteStr(" b")), Ok((CompleteStr(""), (None, CompleteStr("b")))));
+    assert_eq!(prefixed(CompleteStr(" x  b ")), Ok((CompleteStr(unconsumed(" ")), (Some(CompleteStr("x")), CompleteStr("b")))));
+  }
+
+  #[test]
+  fn opt_flag() {
+    named!(flag<CompleteStr, bool>, sep!(sp, opt_flag!(tag!("x"))));
+    assert_eq!(flag(CompleteStr(" x")), Ok((CompleteStr(""), true)));
+    assert_eq!(flag(CompleteStr(" y")), Ok((CompleteStr(" y"), false)));
+
+    named!(pair<CompleteStr, (bool, CompleteStr)>, ws!(pair!(opt_flag!(tag!("x")), tag!("y"))));
+    assert_eq!(pair(CompleteStr(" x y ")), Ok((CompleteStr(unconsumed(" ")), (true, CompleteStr("y")))));
+    assert_eq!(pair(CompleteStr(" y ")), Ok((CompleteStr(unconsumed(" ")), (false, CompleteStr("y")))));
+  }
+
+  #[test]
+  fn binop_level() {
+    use nom::digit;
+
+    named!(factor<CompleteStr, i64>, map_res!(digit, |d: CompleteStr| d.parse::<i64>()));
+    named!(term<CompleteStr, i64>,
+      sep!(sp, binop_level!(factor, one_of!("*/"), |acc: i64, op: char, rhs: i64| {
+        if op == '*' { acc * rhs } else { acc / rhs }
+      }))
+    );
+    named!(expr<CompleteStr, i64>,
+      ws!(binop_level!(term, one_of!("+-"), |acc: i64, op: char, rhs: i64| {
+        if op == '+' { acc + rhs } else { acc - rhs }
+      }))
+    );
+
+    assert_eq!(expr(CompleteStr(" 1 + 2 * 3")), Ok((CompleteStr(""), 7)));
+    assert_eq!(expr(CompleteStr("8 - 2 - 1 ")), Ok((CompleteStr(unconsumed(" ")), 5)));
+    assert_eq!(expr(CompleteStr("2*3*4-12/2/3")), Ok((CompleteStr(""), 22)));
+    assert_eq!(expr(CompleteStr(" 1 + ")), Ok((CompleteStr(unconsumed(" + ")), 1)));
+    assert_eq!(
+      expr(CompleteStr(" + 1")),
+      Err(Err::Error(error_position!(CompleteStr("+ 1"), ErrorKind::Digit)))
+    );
+  }
+
+  #[test]
+  fn cond_reduce() {
+    const FEATURE_DISABLED: u32 = 42;
+
+    fn parser(i: CompleteStr, enabled: bool) -> IResult<CompleteStr, (CompleteStr, CompleteStr)> {
+      ws!(i, cond_reduce!(enabled, FEATURE_DISABLED, pair!(tag!("a"), tag!("b"))))
+    }
+    assert_eq!(parser(CompleteStr(" a b "), true), Ok((CompleteStr(unconsumed(" ")), (CompleteStr("a"), CompleteStr("b")))));
+    assert_eq!(
+      parser(CompleteStr(" a b "), false),
+      Err(Err::Error(error_position!(CompleteStr("a b "), ErrorKind::Custom(FEATURE_DISABLED))))
+    );
+
+    fn default_code(i: CompleteStr, enabled: bool) -> IResult<CompleteStr, CompleteStr> {
+      ws!(i, cond_reduce!(enabled, tag!("a")))
+    }
+    assert_eq!(default_code(CompleteStr(" a"), true), Ok((CompleteStr(""), CompleteStr("a"))));
+    assert_eq!(
+      default_code(CompleteStr(" a"), false),
+      Err(Err::Error(error_position!(CompleteStr("a"), ErrorKind::CondReduce)))
+    );
+  }
+
+  #[test]
+  fn cond_with_error() {
+    use nom::digit;
+
+    fn parser(i: CompleteStr, enabled: bool) -> IResult<CompleteStr, Option<CompleteStr>> {
+      sep!(i, sp, cond_with_error!(enabled, digit))
+    }
+    assert_eq!(parser(CompleteStr("  12 a"), true), Ok((CompleteStr(" a"), Some(CompleteStr("12")))));
+    assert_eq!(
+      parser(CompleteStr("  a"), true),
+      Err(Err::Error(error_position!(CompleteStr("a"), ErrorKind::Digit)))
+    );
+    assert_eq!(parser(CompleteStr("  12 a"), false), Ok((CompleteStr("  12 a"), None)));
+  }
+
+  #[test]
+  fn ws_shebang() {
+    use nom::alpha;
+
+    named!(token<CompleteStr, CompleteStr>, ws_shebang!(alpha));
+    assert_eq!(token(CompleteStr("#!/bin/x\n  token")), Ok((CompleteStr(""), CompleteStr("token"))));
+    assert_eq!(token(CompleteStr("  token")), Ok((CompleteStr(""), CompleteStr("token"))));
+    assert_eq!(token(CompleteStr("#!/bin/x")), Err(Err::Error(error_position!(CompleteStr(""), ErrorKind::Alpha))));
+    assert_eq!(
+      token(CompleteStr(" #!/bin/x\ntoken")),
+      Err(Err::Error(error_position!(CompleteStr("#!/bin/x\ntoken"), ErrorKind::Alpha)))
+    );
+
+    named!(tokens<CompleteStr, Vec<CompleteStr> >, ws_shebang!(many0!(alpha)));
+    assert_eq!(
+      tokens(CompleteStr("#!x\na\n#!y\nb")),
+      Ok((CompleteStr(unconsumed("\n#!y\nb")), vec![CompleteStr("a")]))
+    );
+
+    assert_eq!(super::shebang(&b"#"[..]), Err(Err::Incomplete(Needed::Size(2))));
+    assert_eq!(super::shebang(&b"#!/bin"[..]), Err(Err::Incomplete(Needed::Unknown)));
+    assert_eq!(super::shebang(&b"ls"[..]), Ok((&b"ls"[..], &b""[..])));
+  }
+
+  #[test]
+  fn flat_map() {
+    named!(as_is<CompleteStr, (CompleteStr, CompleteStr)>,
+      ws!(flat_map!(
+        delimited!(tag!("("), take_until!(")"), tag!(")")),
+        tuple!(tag!("a"), tag!("b"))
+      ))
+    );
+    assert_eq!(
+      as_is(CompleteStr(" (ab) ")),
+      Ok((CompleteStr(unconsumed(" ")), (CompleteStr("a"), CompleteStr("b"))))
+    );
+    assert_eq!(
+      as_is(CompleteStr(" ( a b) ")),
+      Err(Err::Error(error_position!(CompleteStr(" a b"), ErrorKind::Tag)))
+    );
+
+    named!(reparse<CompleteStr, (CompleteStr, CompleteStr)>,
+      ws!(flat_map_ws!(
+        delimited!(tag!("("), take_until!(")"), tag!(")")),
+        tuple!(tag!("a"), tag!("b"))
+      ))
+    );
+    assert_eq!(
+      reparse(CompleteStr(" ( a b) ")),
+      Ok((CompleteStr(unconsumed(" ")), (CompleteStr("a"), CompleteStr("b"))))
+    );
+    assert_eq!(
+      reparse(CompleteStr(" (a c)")),
+      Err(Err::Error(error_position!(CompleteStr("c"), ErrorKind::Tag)))
+    );
+  }
+
+  #[test]
+  fn many_same() {
+    use super::MANY_SAME_ERROR;
+
+    named!(run<CompleteStr, (usize, CompleteStr)>, ws!(many_same!(take!(1))));
+    assert_eq!(run(CompleteStr(" a a a")), Ok((CompleteStr(""), (3, CompleteStr("a")))));
+    assert_eq!(run(CompleteStr("****")), Ok((CompleteStr(""), (4, CompleteStr("*")))));
+    assert_eq!(
+      run(CompleteStr(" a a b")),
+      Err(Err::Error(error_position!(CompleteStr("b"), ErrorKind::Custom(MANY_SAME_ERROR))))
+    );
+    assert_eq!(run(CompleteStr("  ")), Err(Err::Error(error_position!(CompleteStr(""), ErrorKind::Eof))));
+
+    named!(stars<CompleteStr, (usize, CompleteStr)>, ws!(many_same!(tag!("*"))));
+    assert_eq!(stars(CompleteStr(" * ** x")), Ok((CompleteStr(unconsumed(" x")), (3, CompleteStr("*")))));
+  }
+
+  #[cfg(feature = "alloc")]
+  #[test]
+  fn tuple_separated_list() {
+    use nom::alpha;
+
+    named!(statement<CompleteStr, (Vec<CompleteStr>, CompleteStr)>,
+      ws!(tuple!(separated_list!(char!(','), alpha), tag!(";")))
+    );
+    assert_eq!(
+      statement(CompleteStr(" a, b, c ;")),
+      Ok((CompleteStr(""), (vec![CompleteStr("a"), CompleteStr("b"), CompleteStr("c")], CompleteStr(";"))))
+    );
+    assert_eq!(
+      statement(CompleteStr("a ,b\n,\tc;")),
+      Ok((CompleteStr(""), (vec![CompleteStr("a"), CompleteStr("b"), CompleteStr("c")], CompleteStr(";"))))
+    );
+    assert_eq!(
+      statement(CompleteStr(" a, b c ;")),
+      Err(Err::Error(error_position!(CompleteStr("c ;"), ErrorKind::Tag)))
+    );
+
+    named!(nested<CompleteStr, (CompleteStr, Vec<(CompleteStr, CompleteStr)>)>,
+      ws!(tuple!(tag!("let"), separated_list!(char!(','), separated_pair!(alpha, char!('='), alpha))))
+    );
+    assert_eq!(
+      nested(CompleteStr("let a = b , c=d")),
+      Ok((CompleteStr(""), (CompleteStr("let"), vec![(CompleteStr("a"), CompleteStr("b")), (CompleteStr("c"), CompleteStr("d"))])))
+    );
+  }
+
+  #[test]
+  fn ws_wrap() {
+    use super::{inline_sp, ws_wrap};
+
+    fn a(i: CompleteStr) -> IResult<CompleteStr, CompleteStr> {
+      tag!(i, "a")
+    }
+    fn b(i: &str) -> IResult<&str, &str> {
+      tag!(i, "b")
+    }
+
+    let parser = ws_wrap(sp, a);
+    assert_eq!(parser(CompleteStr(" \t a \n")), Ok((CompleteStr(""), CompleteStr("a"))));
+    assert_eq!(parser(CompleteStr("a b")), Ok((CompleteStr("b"), CompleteStr("a"))));
+    assert_eq!(parser(CompleteStr(" b")), Err(Err::Error(error_position!(CompleteStr("b"), ErrorKind::Tag))));
+
+    let inline = ws_wrap(inline_sp, a);
+    assert_eq!(inline(CompleteStr(" a \nb")), Ok((CompleteStr("\nb"), CompleteStr("a"))));
+
+    // the trailing separator needs more input to know where it ends
+    let streaming = ws_wrap(sp, b);
+    assert_eq!(streaming(" b c"), Ok(("c", "b")));
+    assert_eq!(streaming(" b "), Err(Err::Incomplete(Needed::Size(1))));
+  }
+
+  #[test]
+  fn peek_len() {
+    named!(len<CompleteStr, usize>, sep!(sp, peek_len!(take!(3))));
+    assert_eq!(len(CompleteStr("  abc")), Ok((CompleteStr("  abc"), 5)));
+    assert_eq!(len(CompleteStr("abcd")), Ok((CompleteStr("abcd"), 3)));
+    assert_eq!(len(CompleteStr("  ab")), Err(Err::Error(error_position!(CompleteStr("ab"), ErrorKind::Eof))));
+
+    named!(pair<CompleteStr, usize>, sep!(sp, peek_len!(pair!(tag!("a"), tag!("b")))));
+    assert_eq!(pair(CompleteStr(" a  b c")), Ok((CompleteStr(" a  b c"), 5)));
+  }
+
+  #[test]
+  fn separator_errors() {
+    use nom::alpha;
+
+    // spaces, but no tabs
+    fn spaces(i: CompleteStr) -> IResult<CompleteStr, CompleteStr> {
+      if i.starts_with('\t') {
+        Err(Err::Error(error_position!(i, ErrorKind::Space)))
+      } else {
+        sp(i)
+      }
+    }
+
+    named!(pair_spaces<CompleteStr, (CompleteStr, CompleteStr)>, sep!(spaces, separator_errors!(pair!(alpha, alpha))));
+
+    assert_eq!(pair_spaces(CompleteStr(" a b")), Ok((CompleteStr(""), (CompleteStr("a"), CompleteStr("b")))));
+    assert_eq!(
+      pair_spaces(CompleteStr(" a 1")),
+      Err(Err::Error(error_position!(CompleteStr("1"), ErrorKind::Alpha)))
+    );
+    assert_eq!(
+      pair_spaces(CompleteStr(" a\tb")),
+      Err(Err::Error(error_position!(CompleteStr("\tb"), ErrorKind::Custom(super::SEPARATOR_ERROR))))
+    );
+
+    // the separator calls of the combinators specific to `sep!` are tagged too
+    named!(not_x<CompleteStr, (CompleteStr, ())>, sep!(spaces, separator_errors!(pair!(alpha, not!(tag!("x"))))));
+    assert_eq!(
+      not_x(CompleteStr(" a\tb")),
+      Err(Err::Error(error_position!(CompleteStr("\tb"), ErrorKind::Custom(super::SEPARATOR_ERROR))))
+    );
+
+    // without it, the separator errors are returned as is
+    named!(untagged<CompleteStr, (CompleteStr, CompleteStr)>, sep!(spaces, pair!(alpha, alpha)));
+    assert_eq!(
+      untagged(CompleteStr(" a\tb")),
+      Err(Err::Error(error_position!(CompleteStr("\tb"), ErrorKind::Space)))
+    );
+
+    // and custom codes are kept
+    use super::{StickyNewlineSeparator, STICKY_NEWLINE_ERROR};
+    let separator = StickyNewlineSeparator::new();
+    let sticky = |i| sep_with!(i, &separator, separator_errors!(tuple!(alpha, alpha, alpha)));
+    assert_eq!(
+      sticky(CompleteStr("a\nb c")),
+      Err(Err::Error(error_position!(CompleteStr(" c"), ErrorKind::Custom(STICKY_NEWLINE_ERROR))))
+    );
+  }
+
+  #[test]
+  fn without_ws() {
+    use nom::{alpha, digit};
+
+    named!(packed<CompleteStr, (CompleteStr, (CompleteStr, CompleteStr), CompleteStr)>,
+      ws!(tuple!(alpha, without_ws!(pair!(tag!("#"), digit)), alpha))
+    );
+    assert_eq!(
+      packed(CompleteStr(" a #12 b")),
+      Ok((CompleteStr(""), (CompleteStr("a"), (CompleteStr("#"), CompleteStr("12")), CompleteStr("b"))))
+    );
+    assert_eq!(
+      packed(CompleteStr(" a # 12 b")),
+      Err(Err::Error(error_position!(CompleteStr(" 12 b"), ErrorKind::Digit)))
+    );
+  }
+
+  #[test]
+  fn do_parse_no_ws() {
+    use nom::alpha;
+
+    named!(record<CompleteStr, (CompleteStr, CompleteStr, CompleteStr)>,
+      ws!(do_parse!(
+        name: alpha >>
+        tag!(":") >>
+        raw: no_ws!(take!(4)) >>
+        rest: alpha >>
+        (name, raw, rest)
+      ))
+    );
+
+    assert_eq!(
+      record(CompleteStr(" abc :  x end")),
+      Ok((CompleteStr(""), (CompleteStr("abc"), CompleteStr("  x "), CompleteStr("end"))))
+    );
+    assert_eq!(
+      record(CompleteStr("abc:1234end")),
+      Ok((CompleteStr(""), (CompleteStr("abc"), CompleteStr("1234"), CompleteStr("end"))))
+    );
+  }
+
+  #[test]
+  fn offset_of() {
+    use nom::alpha;
+
+    type Located<'a> = (CompleteStr<'a>, usize);
+
+    fn tokens(i: CompleteStr) -> IResult<CompleteStr, (Located, Located)> {
+      ws!(i, pair!(offset_of!(i, alpha), offset_of!(i, alpha)))
+    }
+
+    assert_eq!(
+      tokens(CompleteStr(" a  bb")),
+      Ok((CompleteStr(""), ((CompleteStr("a"), 1), (CompleteStr("bb"), 4))))
+    );
+    assert_eq!(
+      tokens(CompleteStr("a\n\tb ")),
+      Ok((CompleteStr(unconsumed(" ")), ((CompleteStr("a"), 0), (CompleteStr("b"), 3))))
+    );
+  }
+
+  #[test]
+  fn keyword_ci() {
+    named!(select<CompleteStr, CompleteStr>, ws!(keyword_ci!("SELECT")));
+
+    assert_eq!(select(CompleteStr("  select ")), Ok((CompleteStr(unconsumed(" ")), CompleteStr("select"))));
+    assert_eq!(select(CompleteStr("  SELECT\t")), Ok((CompleteStr(unconsumed("\t")), CompleteStr("SELECT"))));
+    assert_eq!(select(CompleteStr("sElEcT")), Ok((CompleteStr(""), CompleteStr("sElEcT"))));
+    assert_eq!(
+      select(CompleteStr("  selected")),
+      Err(Err::Error(error_position!(CompleteStr("selected"), ErrorKind::Tag)))
+    );
+    assert_eq!(
+      select(CompleteStr("  sel")),
+      Err(Err::Error(error_position!(CompleteStr("sel"), ErrorKind::Eof)))
+    );
+    assert!(select(CompleteStr("select_")).is_err());
+
+    named!(streaming<&str, &str>, keyword_ci!("select"));
+    assert_eq!(streaming("SELECT"), Err(Err::Incomplete(Needed::Unknown)));
+  }
+
+  #[test]
+  fn opt_prefixes() {
+    use nom::alpha;
+
+    named!(decl<CompleteStr, ([bool; 2], CompleteStr)>,
+      ws!(opt_prefixes!([tag!("pub"), tag!("static")], alpha))
+    );
+
+    assert_eq!(decl(CompleteStr(" pub name")), Ok((CompleteStr(""), ([true, false], CompleteStr("name")))));
+    assert_eq!(decl(CompleteStr(" static name")), Ok((CompleteStr(""), ([false, true], CompleteStr("name")))));
+    assert_eq!(decl(CompleteStr(" name")), Ok((CompleteStr(""), ([false, false], CompleteStr("name")))));
+    assert_eq!(decl(CompleteStr("pub\tstatic\nname ")), Ok((CompleteStr(unconsumed(" ")), ([true, true], CompleteStr("name")))));
+    // prefixes must be in order
+    assert_eq!(decl(CompleteStr("static pub name")), Ok((CompleteStr(unconsumed(" name")), ([false, true], CompleteStr("pub")))));
+  }
+
+  #[test]
+  fn alt_tagged() {
+    use nom::{alpha, digit};
+
+    named!(token<CompleteStr, (usize, CompleteStr)>, ws!(alt_tagged!(tag!("let") | digit | alpha)));
+
+    assert_eq!(token(CompleteStr(" 42 ")), Ok((CompleteStr(unconsumed(" ")), (1, CompleteStr("42")))));
+    assert_eq!(token(CompleteStr("let")), Ok((CompleteStr(""), (0, CompleteStr("let")))));
+    assert_eq!(token(CompleteStr(" x")), Ok((CompleteStr(""), (2, CompleteStr("x")))));
+    assert_eq!(
+      token(CompleteStr(" ;")),
+      Err(Err::Error(error_position!(CompleteStr(" ;"), ErrorKind::Alt)))
+    );
+  }
+
+  #[test]
+  fn alt_into() {
+    named!(small<CompleteStr, u8>, value!(1, tag!("small")));
+    named!(big<CompleteStr, u16>, value!(1000, tag!("big")));
+
+    named!(size<CompleteStr, u32>, ws!(alt_into!(small | big | value!(70_000u32, tag!("huge")))));
+    assert_eq!(size(CompleteStr(" small ")), Ok((CompleteStr(unconsumed(" ")), 1)));
+    assert_eq!(size(CompleteStr("\tbig")), Ok((CompleteStr(""), 1000)));
+    assert_eq!(size(CompleteStr("huge")), Ok((CompleteStr(""), 70_000)));
+    assert_eq!(
+      size(CompleteStr(" tiny")),
+      Err(Err::Error(error_position!(CompleteStr(" tiny"), ErrorKind::Alt)))
+    );
+  }
+
+  #[test]
+  fn alt_ambiguous() {
+    use nom::alpha;
+
+    named!(ambiguous<CompleteStr, (CompleteStr, usize)>,
+      ws!(alt_ambiguous!(tag!("ab") | alpha | tag!("b") | tag!("a")))
+    );
+    assert_eq!(ambiguous(CompleteStr("  ab")), Ok((CompleteStr(""), (CompleteStr("ab"), 3))));
+    assert_eq!(ambiguous(CompleteStr("  bc")), Ok((CompleteStr(""), (CompleteStr("bc"), 2))));
+    assert_eq!(ambiguous(CompleteStr("  cd")), Ok((CompleteStr(""), (CompleteStr("cd"), 1))));
+    assert_eq!(
+      ambiguous(CompleteStr("  1")),
+      Err(Err::Error(error_position!(CompleteStr("  1"), ErrorKind::Alt)))
+    );
+  }
+
+  #[test]
+  fn recognize_trim() {
+    use super::inline_sp;
+
+    named!(value<CompleteStr, CompleteStr>, ws!(recognize_trim!(take_while!(|c| c != ';'))));
+    named!(spans<CompleteStr, (CompleteStr, CompleteStr)>,
+      ws!(pair!(recognize_trim!(take_while!(|c| c != ';')), recognize!(take_while!(|c| c != ';'))))
+    );
+
+    assert_eq!(value(CompleteStr(" abc   ")), Ok((CompleteStr(""), CompleteStr("abc"))));
+    assert_eq!(value(CompleteStr(" a b \t\n;")), Ok((CompleteStr(";"), CompleteStr("a b"))));
+    assert_eq!(value(CompleteStr("   ")), Ok((CompleteStr(""), CompleteStr(""))));
+    assert_eq!(value(CompleteStr("é \u{e9}")), Ok((CompleteStr(""), CompleteStr("é \u{e9}"))));
+    assert_eq!(spans(CompleteStr(" ab ")), Ok((CompleteStr(""), (CompleteStr("ab"), CompleteStr("")))));
+
+    // the trimmed part is what the separator consumes, like comments
+    named!(commented<CompleteStr, CompleteStr>, sep!(comment_sp, recognize_trim!(take_while!(|c| c != ';'))));
+    assert_eq!(
+      commented(CompleteStr(" a /* b */ c // d\n /* e */ ;")),
+      Ok((CompleteStr(";"), CompleteStr("a /* b */ c")))
+    );
+
+    // and only that, with a separator not consuming line endings
+    named!(inline<CompleteStr, CompleteStr>, sep!(inline_sp, recognize_trim!(take_while!(|c| c != ';'))));
+    assert_eq!(inline(CompleteStr(" a \n \t;")), Ok((CompleteStr(";"), CompleteStr("a \n"))));
+
+    // on partial input too
+    named!(bytes<&[u8], &[u8]>, sep!(sp, recognize_trim!(take_while!(|c| c != b';'))));
+    assert_eq!(bytes(&b" a b \t;"[..]), Ok((&b";"[..], &b"a b"[..])));
+  }
+
+  #[cfg(feature = "alloc")]
+  #[test]
+  fn recognize_many0() {
+    named!(span<CompleteStr, CompleteStr>, ws!(recognize!(many0!(tag!("a")))));
+    assert_eq!(span(CompleteStr("  a a a  ")), Ok((CompleteStr(unconsumed("  ")), CompleteStr("a a a"))));
+    assert_eq!(span(CompleteStr(" a\n\ta b")), Ok((CompleteStr(unconsumed(" b")), CompleteStr("a\n\ta"))));
+    assert_eq!(span(CompleteStr("  b")), Ok((CompleteStr("b"), CompleteStr(""))));
+
+    named!(pairs<CompleteStr, (CompleteStr, CompleteStr)>,
+      ws!(pair!(recognize!(many0!(tag!("a"))), recognize!(many1!(tag!("b")))))
+    );
+    assert_eq!(pairs(CompleteStr(" a a  b b ")), Ok((CompleteStr(unconsumed(" ")), (CompleteStr("a a"), CompleteStr("b b")))));
+  }
+
+  #[cfg(feature = "alloc")]
+  #[test]
+  fn number_list() {
+    use nom::digit;
+    use super::inline_sp;
+
+    named!(numbers<CompleteStr, Vec<u32> >, ws!(number_list!(digit)));
+    assert_eq!(numbers(CompleteStr(" 1 2 3 ")), Ok((CompleteStr(unconsumed(" ")), vec![1, 2, 3])));
+    assert_eq!(numbers(CompleteStr("")), Ok((CompleteStr(""), vec![])));
+    assert_eq!(numbers(CompleteStr(" 1 x 3")), Ok((CompleteStr(unconsumed(" x 3")), vec![1])));
+    assert_eq!(numbers(CompleteStr("1 99999999999")), Ok((CompleteStr(unconsumed(" 99999999999")), vec![1])));
+
+    // the list can be followed by other parsers
+    named!(statement<CompleteStr, (Vec<u32>, CompleteStr)>, ws!(pair!(number_list!(digit), tag!(";"))));
+    assert_eq!(statement(CompleteStr("1 2;")), Ok((CompleteStr(""), (vec![1, 2], CompleteStr(";")))));
+    assert_eq!(statement(CompleteStr(" 1\n2 ;")), Ok((CompleteStr(""), (vec![1, 2], CompleteStr(";")))));
+    assert_eq!(
+      statement(CompleteStr(" 1 x 3;")),
+      Err(Err::Error(error_position!(CompleteStr("x 3;"), ErrorKind::Tag)))
+    );
+
+    // byte slices are converted too
+    named!(bytes<&[u8], Vec<u16> >, sep!(inline_sp, number_list!(digit)));
+    assert_eq!(bytes(&b" 10 20;"[..]), Ok((&b";"[..], vec![10, 20])));
+
+    named!(line<CompleteStr, Vec<u8> >, sep!(inline_sp, number_list!(digit)));
+    assert_eq!(line(CompleteStr("1 2 \n3")), Ok((CompleteStr(" \n3"), vec![1, 2])));
+  }
+
+  #[cfg(feature = "alloc")]
+  #[test]
+  fn count() {
+    use nom::digit;
+
+    named!(record<CompleteStr, Vec<CompleteStr> >, ws!(count!(digit, 4)));
+
+    let (rest, fields) = record(CompleteStr(" 1 22\n333 4444 55")).unwrap();
+    assert_eq!(rest, CompleteStr(unconsumed(" 55")));
+    assert_eq!(fields, vec![CompleteStr("1"), CompleteStr("22"), CompleteStr("333"), CompleteStr("4444")]);
+    assert_eq!(fields.capacity(), 4);
+
+    assert_eq!(
+      record(CompleteStr(" 1 2 a")),
+      Err(Err::Error(error_position!(CompleteStr("1 2 a"), ErrorKind::Count)))
+    );
+  }
+
+  #[test]
+  // the range check in nom's `take_while_m_n!`
+  #[allow(clippy::manual_range_contains)]
+  fn take_while_m_n() {
+    use nom::is_alphanumeric;
+
+    named!(word<CompleteByteSlice, CompleteByteSlice>, ws!(take_while_m_n!(2, 4, is_alphanumeric)));
+    named!(partial<&[u8], &[u8]>, ws!(take_while_m_n!(2, 4, is_alphanumeric)));
+
+    assert_eq!(word(CompleteByteSlice(b"  abcd")), Ok((CompleteByteSlice(b""), CompleteByteSlice(b"abcd"))));
+    assert_eq!(word(CompleteByteSlice(b"  abc")), Ok((CompleteByteSlice(b""), CompleteByteSlice(b"abc"))));
+    assert_eq!(word(CompleteByteSlice(b" abcdef")), Ok((CompleteByteSlice(b"ef"), CompleteByteSlice(b"abcd"))));
+    assert_eq!(
+      word(CompleteByteSlice(b"  a")),
+      Err(Err::Error(error_position!(CompleteByteSlice(b"a"), ErrorKind::TakeWhileMN)))
+    );
+    assert_eq!(
+      word(CompleteByteSlice(b"  a b")),
+      Err(Err::Error(error_position!(CompleteByteSlice(b"a b"), ErrorKind::TakeWhileMN)))
+    );
+
+    assert_eq!(partial(&b" ab;"[..]), Ok((&b";"[..], &b"ab"[..])));
+    assert_eq!(partial(&b" abc"[..]), Err(Err::Incomplete(Needed::Size(1))));
+
+    named!(chars<CompleteStr, CompleteStr>, ws!(take_while_m_n!(2, 4, |c: char| c.is_alphanumeric())));
+    assert_eq!(chars(CompleteStr("  abcd")), Ok((CompleteStr(""), CompleteStr("abcd"))));
+    assert_eq!(chars(CompleteStr("\tab")), Ok((CompleteStr(""), CompleteStr("ab"))));
+    assert_eq!(chars(CompleteStr(" abcdef ")), Ok((CompleteStr(unconsumed("ef ")), CompleteStr("abcd"))));
+    assert_eq!(
+      chars(CompleteStr("  a")),
+      Err(Err::Error(error_position!(CompleteStr("a"), ErrorKind::TakeWhileMN)))
+    );
+  }
+
+  #[test]
+  fn require_final_newline() {
+    use nom::alpha;
+
+    named!(strict<CompleteStr, CompleteStr>, require_final_newline!(alpha));
+    named!(lenient<CompleteStr, CompleteStr>, require_final_newline!(alpha, true));
+
+    assert_eq!(strict(CompleteStr("content\n")), Ok((CompleteStr(""), CompleteStr("content"))));
+    assert_eq!(strict(CompleteStr("content \t\r\n")), Ok((CompleteStr(""), CompleteStr("content"))));
+    assert_eq!(
+      strict(CompleteStr("content")),
+      Err(Err::Error(error_position!(CompleteStr(""), ErrorKind::CrLf)))
+    );
+    assert_eq!(
+      strict(CompleteStr("content\n\n")),
+      Err(Err::Error(error_position!(CompleteStr("\n"), ErrorKind::Eof)))
+    );
+    assert_eq!(
+      strict(CompleteStr("content more\n")),
+      Err(Err::Error(error_position!(CompleteStr("more\n"), ErrorKind::CrLf)))
+    );
+
+    assert_eq!(lenient(CompleteStr("content\n\n \n")), Ok((CompleteStr(""), CompleteStr("content"))));
+    assert_eq!(
+      lenient(CompleteStr("content\n\nmore")),
+      Err(Err::Error(error_position!(CompleteStr("more"), ErrorKind::Eof)))
+    );
+
+    // on partial input, the end of the file is not known yet
+    named!(partial<&str, &str>, require_final_newline!(alpha));
+    assert_eq!(partial("content\n"), Err(Err::Error(error_position!("", ErrorKind::Eof))));
+  }
+
+  #[test]
+  fn sp_resumable() {
+    use super::{sp_resumable, SpState};
+
+    let mut state = SpState::new();
+    assert_eq!(sp_resumable(&mut state, "  "), Ok(("", (2, true))));
+    assert!(state.is_pending());
+    assert_eq!(sp_resumable(&mut state, "  token"), Ok(("token", (2, false))));
+    assert!(!state.is_pending());
+    assert_eq!(state.run_len(), 4);
+
+    // a new run starts after the previous one ended
+    assert_eq!(sp_resumable(&mut state, " \n"), Ok(("", (2, true))));
+    assert_eq!(state.run_len(), 2);
+
+    // a complete input cannot continue
+    let mut state = SpState::new();
+    assert_eq!(sp_resumable(&mut state, CompleteStr("  ")), Ok((CompleteStr(""), (2, false))));
+    assert_eq!(sp_resumable(&mut state, CompleteStr("a")), Ok((CompleteStr("a"), (0, false))));
+    assert_eq!(state.run_len(), 0);
+  }
+
+  #[test]
+  fn sp_continuation() {
+    use nom::alpha;
+    use super::sp_continuation;
+
+    named!(words<CompleteStr, (CompleteStr, CompleteStr)>, sep!(sp_continuation, pair!(alpha, alpha)));
+    assert_eq!(words(CompleteStr("a \\\n b")), Ok((CompleteStr(""), (CompleteStr("a"), CompleteStr("b")))));
+    assert_eq!(words(CompleteStr("a\t\\\r\nb")), Ok((CompleteStr(""), (CompleteStr("a"), CompleteStr("b")))));
+    assert_eq!(
+      words(CompleteStr("a \n b")),
+      Err(Err::Error(error_position!(CompleteStr("\n b"), ErrorKind::Alpha)))
+    );
+
+    assert_eq!(sp_continuation(CompleteStr(" \n b")), Ok((CompleteStr("\n b"), CompleteStr(" "))));
+    assert_eq!(sp_continuation(CompleteStr(" \\b")), Ok((CompleteStr("\\b"), CompleteStr(" "))));
+    assert_eq!(sp_continuation(CompleteStr(" \\")), Ok((CompleteStr("\\"), CompleteStr(" "))));
+    assert_eq!(sp_continuation(CompleteStr(" \t")), Ok((CompleteStr(""), CompleteStr(" \t"))));
+    assert_eq!(sp_continuation(" \\\n"), Err(Err::Incomplete(Needed::Size(1))));
   }
 
+  #[cfg(feature = "alloc")]
   #[test]
-  fn tag() {
-    named!(abc, ws!(tag!("abc")));
+  fn list_until() {
+    use nom::alpha;
 
-    assert_eq!(abc(&b" \t abc def"[..]), Ok((&b"def"[..], &b"abc"[..])));
-  }
+    named!(words<CompleteStr, Vec<CompleteStr> >, sep!(sp, list_until!(alpha, tag_bounded!("END"))));
 
-  #[test]
-  fn pair() {
-    named!(pair_2<&[u8], (&[u8], &[u8]) >,
-      ws!(pair!( take!(3), tag!("de") ))
+    assert_eq!(
+      words(CompleteStr(" a b c END")),
+      Ok((CompleteStr(""), vec![CompleteStr("a"), CompleteStr("b"), CompleteStr("c")]))
     );
-
+    assert_eq!(words(CompleteStr(" END rest")), Ok((CompleteStr(" rest"), vec![])));
     assert_eq!(
-      pair_2(&b" \t abc de fg"[..]),
-      Ok((&b"fg"[..], (&b"abc"[..], &b"de"[..])))
+      words(CompleteStr(" a ENDING END")),
+      Ok((CompleteStr(""), vec![CompleteStr("a"), CompleteStr("ENDING")]))
+    );
+    assert_eq!(
+      words(CompleteStr(" a b")),
+      Err(Err::Error(error_position!(CompleteStr(""), ErrorKind::ManyTill)))
     );
   }
 
+  #[cfg(feature = "alloc")]
   #[test]
-  fn preceded() {
-    named!(prec<&[u8], &[u8] >,
-      ws!(preceded!( take!(3), tag!("de") ))
+  fn many_till_peek() {
+    use nom::alpha;
+
+    named!(until_semicolon<CompleteStr, Vec<CompleteStr> >, sep!(sp, many_till_peek!(alpha, char!(';'))));
+    assert_eq!(
+      until_semicolon(CompleteStr(" a b ;")),
+      Ok((CompleteStr(" ;"), vec![CompleteStr("a"), CompleteStr("b")]))
+    );
+    assert_eq!(until_semicolon(CompleteStr(";")), Ok((CompleteStr(";"), vec![])));
+    assert_eq!(
+      until_semicolon(CompleteStr(" a 1 ;")),
+      Err(Err::Error(error_position!(CompleteStr(" 1 ;"), ErrorKind::ManyTill)))
+    );
+    assert_eq!(
+      until_semicolon(CompleteStr(" a b")),
+      Err(Err::Error(error_position!(CompleteStr(""), ErrorKind::ManyTill)))
     );
 
-    assert_eq!(prec(&b" \t abc de fg"[..]), Ok((&b"fg"[..], &b"de"[..])));
+    named!(statement<CompleteStr, (Vec<CompleteStr>, char)>, ws!(pair!(many_till_peek!(alpha, char!(';')), char!(';'))));
+    assert_eq!(
+      statement(CompleteStr(" a b ; ")),
+      Ok((CompleteStr(unconsumed(" ")), (vec![CompleteStr("a"), CompleteStr("b")], ';')))
+    );
   }
 
+  #[cfg(feature = "alloc")]
   #[test]
-  fn terminated() {
-    named!(term<&[u8], &[u8] >,
-      ws!(terminated!( take!(3), tag!("de") ))
+  fn flags_and_kv() {
+    use nom::{alpha, digit};
+
+    named!(options<CompleteStr, Vec<(CompleteStr, Option<CompleteStr>)> >,
+      ws!(flags_and_kv!(
+        no_ws!(recognize!(preceded!(alt!(tag!("--") | tag!("-")), alpha))),
+        tag!("="),
+        digit
+      ))
     );
 
-    assert_eq!(term(&b" \t abc de fg"[..]), Ok((&b"fg"[..], &b"abc"[..])));
+    assert_eq!(
+      options(CompleteStr(" --verbose --level=3 -q ")),
+      Ok((CompleteStr(unconsumed(" ")), vec![
+        (CompleteStr("--verbose"), None),
+        (CompleteStr("--level"), Some(CompleteStr("3"))),
+        (CompleteStr("-q"), None),
+      ]))
+    );
+    assert_eq!(options(CompleteStr("")), Ok((CompleteStr(""), vec![])));
+    // options must be separated by whitespace
+    assert_eq!(options(CompleteStr("-a-b")), Ok((CompleteStr("-b"), vec![(CompleteStr("-a"), None)])));
   }
 
   #[test]
-  fn tuple() {
-    //trace_macros!(true);
-    named!(tuple_2<&[u8], (&[u8], &[u8]) >,
-      ws!(tuple!( take!(3), tag!("de") ))
-    );
-    //trace_macros!(false);
+  fn sp_sanitize() {
+    use nom::alpha;
+    use super::sp_sanitize;
+
+    named!(words<CompleteStr, (CompleteStr, CompleteStr)>, sep!(sp_sanitize, pair!(alpha, alpha)));
 
     assert_eq!(
-      tuple_2(&b" \t abc de fg"[..]),
-      Ok((&b"fg"[..], (&b"abc"[..], &b"de"[..])))
+      words(CompleteStr("admin\u{202E} \u{2066}user\u{2069}")),
+      Ok((CompleteStr("\u{2069}"), (CompleteStr("admin"), CompleteStr("user"))))
+    );
+    assert_eq!(
+      words(CompleteStr("a\u{200B}\u{200D}\u{FEFF}\u{200F}b")),
+      Ok((CompleteStr(""), (CompleteStr("a"), CompleteStr("b"))))
     );
+    assert_eq!(
+      sp_sanitize(CompleteStr("\u{202A}\u{202B}\u{202C}\u{202D}\u{2067}\u{2068}\u{200C}\u{2060}\u{200E}x")),
+      Ok((CompleteStr("x"), CompleteStr("\u{202A}\u{202B}\u{202C}\u{202D}\u{2067}\u{2068}\u{200C}\u{2060}\u{200E}")))
+    );
+    assert_eq!(sp_sanitize(CompleteStr("\u{2029}x")), Ok((CompleteStr("\u{2029}x"), CompleteStr(""))));
   }
 
   #[test]
-  fn levels() {
-    //trace_macros!(true);
-    named!(level_2<&[u8], (&[u8], (&[u8], &[u8])) >,
-      ws!(pair!(take!(3), tuple!( tag!("de"), tag!("fg ") )))
+  fn sp_strict_indent() {
+    use nom::alpha;
+    use super::{sp_strict_indent, MIXED_INDENT_ERROR};
+
+    assert_eq!(sp_strict_indent(CompleteStr("    x")), Ok((CompleteStr("x"), CompleteStr("    "))));
+    assert_eq!(sp_strict_indent(CompleteStr("\t\tx")), Ok((CompleteStr("x"), CompleteStr("\t\t"))));
+    assert_eq!(
+      sp_strict_indent(CompleteStr(" \t x")),
+      Err(Err::Error(error_position!(CompleteStr(" \t x"), ErrorKind::Custom(MIXED_INDENT_ERROR))))
     );
-    //trace_macros!(false);
+    assert_eq!(
+      sp_strict_indent(CompleteStr("\t\r\n  \tx")),
+      Err(Err::Error(error_position!(CompleteStr("  \tx"), ErrorKind::Custom(MIXED_INDENT_ERROR))))
+    );
+    assert_eq!(sp_strict_indent("  "), Err(Err::Incomplete(Needed::Size(1))));
 
+    named!(words<CompleteStr, (CompleteStr, CompleteStr)>, sep!(sp_strict_indent, pair!(alpha, alpha)));
+    assert_eq!(words(CompleteStr("a\n\tb")), Ok((CompleteStr(""), (CompleteStr("a"), CompleteStr("b")))));
     assert_eq!(
-      level_2(&b" \t abc de fg \t hi "[..]),
-      Ok((&b"hi "[..], (&b"abc"[..], (&b"de"[..], &b"fg "[..]))))
+      words(CompleteStr("a\n\t b")),
+      Err(Err::Error(error_position!(CompleteStr("\t b"), ErrorKind::Custom(MIXED_INDENT_ERROR))))
     );
   }
 
+  #[cfg(feature = "alloc")]
   #[test]
-  fn do_parse() {
-    fn ret_int1(i: &[u8]) -> IResult<&[u8], u8> {
-      Ok((i, 1))
-    };
-    fn ret_int2(i: &[u8]) -> IResult<&[u8], u8> {
-      Ok((i, 2))
-    };
+  fn balanced() {
+    use nom::alpha;
+    use super::Balanced::{Group, Leaf};
 
-    //trace_macros!(true);
-    named!(do_parser<&[u8], (u8, u8)>,
-      ws!(do_parse!(
-        tag!("abcd")       >>
-        opt!(tag!("abcd")) >>
-        aa: ret_int1       >>
-        tag!("efgh")       >>
-        bb: ret_int2       >>
-        tag!("efgh")       >>
-        (aa, bb)
-      ))
-    );
-
-    //trace_macros!(false);
+    named!(tree<CompleteStr, super::Balanced<CompleteStr> >, ws!(balanced!(char!('('), char!(')'), alpha)));
 
     assert_eq!(
-      do_parser(&b"abcd abcd\tefghefghX"[..]),
-      Ok((&b"X"[..], (1, 2)))
+      tree(CompleteStr("( a ( b ) c )")),
+      Ok((CompleteStr(""), Group(vec![
+        Leaf(CompleteStr("a")),
+        Group(vec![Leaf(CompleteStr("b"))]),
+        Leaf(CompleteStr("c")),
+      ])))
     );
     assert_eq!(
-      do_parser(&b"abcd\tefgh      efgh X"[..]),
-      Ok((&b"X"[..], (1, 2)))
+      tree(CompleteStr("(((a)) ()b)")),
+      Ok((CompleteStr(""), Group(vec![
+        Group(vec![Group(vec![Leaf(CompleteStr("a"))])]),
+        Group(vec![]),
+        Leaf(CompleteStr("b")),
+      ])))
     );
     assert_eq!(
-      do_parser(&b"abcd  ab"[..]),
-      Err(Err::Incomplete(Needed::Size(4)))
+      tree(CompleteStr("( a ( b c )")),
+      Err(Err::Error(error_position!(CompleteStr(""), ErrorKind::Eof)))
     );
     assert_eq!(
-      do_parser(&b" abcd\tefgh\tef"[..]),
-      Err(Err::Incomplete(Needed::Size(4)))
+      tree(CompleteStr("( a ; )")),
+      Err(Err::Error(error_position!(CompleteStr("; )"), ErrorKind::Char)))
     );
   }
 
+  #[cfg(feature = "alloc")]
   #[test]
-  fn permutation() {
-    //trace_macros!(true);
-    named!(
-      perm<(&[u8], &[u8], &[u8])>,
-      ws!(permutation!(tag!("abcd"), tag!("efg"), tag!("hi")))
-    );
-    //trace_macros!(false);
-
-    let expected = (&b"abcd"[..], &b"efg"[..], &b"hi"[..]);
+  fn bracketed_list() {
+    use nom::alpha;
 
-    let a = &b"abcd\tefg \thijk"[..];
-    assert_eq!(perm(a), Ok((&b"jk"[..], expected)));
-    let b = &b"  efg  \tabcdhi jk"[..];
-    assert_eq!(perm(b), Ok((&b"jk"[..], expected)));
-    let c = &b" hi   efg\tabcdjk"[..];
-    assert_eq!(perm(c), Ok((&b"jk"[..], expected)));
+    named!(list<CompleteStr, Vec<CompleteStr> >, ws!(bracketed_list!(char!('['), char!(','), alpha, char!(']'))));
 
-    let d = &b"efg  xyzabcdefghi"[..];
+    assert_eq!(list(CompleteStr("[ ]")), Ok((CompleteStr(""), vec![])));
+    assert_eq!(list(CompleteStr("[]")), Ok((CompleteStr(""), vec![])));
+    assert_eq!(list(CompleteStr("[ a , b ]")), Ok((CompleteStr(""), vec![CompleteStr("a"), CompleteStr("b")])));
+    assert_eq!(list(CompleteStr(" [a,\nb,c] d")), Ok((CompleteStr(unconsumed(" d")), vec![CompleteStr("a"), CompleteStr("b"), CompleteStr("c")])));
     assert_eq!(
-      perm(d),
-      Err(Err::Error(nom_ws_error_node_position!(
-        &b"efg  xyzabcdefghi"[..],
-        ErrorKind::Permutation,
-        nom_ws_error_position!(&b"  xyzabcdefghi"[..], ErrorKind::Permutation)
-      )))
+      list(CompleteStr("[a, b,]")),
+      Err(Err::Error(error_position!(CompleteStr(",]"), ErrorKind::Char)))
     );
-
-    let e = &b" efg \tabc"[..];
-    assert_eq!(perm(e), Err(Err::Incomplete(Needed::Size(4))));
   }
 
   #[cfg(feature = "alloc")]
-  #[derive(Debug, Clone, PartialEq)]
-  pub struct ErrorStr(String);
+  #[test]
+  fn separated_list_flex() {
+    use nom::digit;
 
-  #[cfg(feature = "alloc")]
-  impl From<u32> for ErrorStr {
-    fn from(i: u32) -> Self {
-      ErrorStr(format!("custom error code: {}", i))
-    }
+    named!(list<CompleteStr, Vec<CompleteStr> >, ws!(separated_list_flex!(digit)));
+    assert_eq!(
+      list(CompleteStr("1 2, 3 ,4")),
+      Ok((CompleteStr(""), vec![CompleteStr("1"), CompleteStr("2"), CompleteStr("3"), CompleteStr("4")]))
+    );
+    assert_eq!(list(CompleteStr("1,2,")), Ok((CompleteStr(","), vec![CompleteStr("1"), CompleteStr("2")])));
+    assert_eq!(list(CompleteStr("1,,2")), Ok((CompleteStr(",,2"), vec![CompleteStr("1")])));
+    assert_eq!(list(CompleteStr("")), Ok((CompleteStr(""), vec![])));
   }
 
   #[cfg(feature = "alloc")]
-  impl<'a> From<&'a str> for ErrorStr {
-    fn from(i: &'a str) -> Self {
-      ErrorStr(format!("custom error message: {}", i))
-    }
+  #[test]
+  fn separated_list_budget() {
+    use nom::alpha;
+    use super::LIST_BUDGET_ERROR;
+
+    named!(list<CompleteStr, Vec<CompleteStr> >, ws!(separated_list_budget!(10, tag!(","), alpha)));
+
+    assert_eq!(
+      list(CompleteStr(" a , b,c ")),
+      Ok((CompleteStr(unconsumed(" ")), vec![CompleteStr("a"), CompleteStr("b"), CompleteStr("c")]))
+    );
+    assert_eq!(list(CompleteStr("a,a,a,a,a,a,a,a,a,a")).map(|(_, v)| v.len()), Ok(10));
+
+    // the list stops on an element that does not parse, even at the budget
+    named!(two<CompleteStr, Vec<CompleteStr> >, ws!(separated_list_budget!(2, tag!(","), alpha)));
+    assert_eq!(two(CompleteStr("a,b,1")), Ok((CompleteStr(",1"), vec![CompleteStr("a"), CompleteStr("b")])));
+    assert_eq!(
+      two(CompleteStr("a,b,c")),
+      Err(Err::Failure(error_position!(CompleteStr("c"), ErrorKind::Custom(LIST_BUDGET_ERROR))))
+    );
+
+    let input = vec!["a"; 1000].join(", ");
+    assert_eq!(
+      list(CompleteStr(&input)),
+      Err(Err::Failure(error_position!(CompleteStr(&input[30..]), ErrorKind::Custom(LIST_BUDGET_ERROR))))
+    );
   }
 
   #[cfg(feature = "alloc")]
   #[test]
-  fn alt() {
-    fn work(input: &[u8]) -> IResult<&[u8], &[u8], ErrorStr> {
-      Ok((&b""[..], input))
-    }
+  fn separated_list_spanned() {
+    use nom::alpha;
 
-    #[allow(unused_variables)]
-    fn dont_work(input: &[u8]) -> IResult<&[u8], &[u8], ErrorStr> {
-      use Context;
-      Err(Err::Error(Context::Code(
-        &b""[..],
-        ErrorKind::Custom(ErrorStr("abcd".to_string())),
-      )))
+    fn list(i: CompleteStr) -> IResult<CompleteStr, Vec<(usize, CompleteStr)>> {
+      ws!(i, separated_list_spanned!(i, char!(','), alpha))
     }
 
-    fn work2(input: &[u8]) -> IResult<&[u8], &[u8], ErrorStr> {
-      Ok((input, &b""[..]))
-    }
+    assert_eq!(
+      list(CompleteStr(" a, b, c")),
+      Ok((CompleteStr(""), vec![(1, CompleteStr("a")), (4, CompleteStr("b")), (7, CompleteStr("c"))]))
+    );
+    assert_eq!(list(CompleteStr("ab ,\tcd")), Ok((CompleteStr(""), vec![(0, CompleteStr("ab")), (5, CompleteStr("cd"))])));
+    assert_eq!(list(CompleteStr(" ")), Ok((CompleteStr(unconsumed(" ")), vec![])));
 
-    fn alt1(i: &[u8]) -> IResult<&[u8], &[u8], ErrorStr> {
-      alt!(i, dont_work | dont_work)
-    }
-    fn alt2(i: &[u8]) -> IResult<&[u8], &[u8], ErrorStr> {
-      alt!(i, dont_work | work)
-    }
-    fn alt3(i: &[u8]) -> IResult<&[u8], &[u8], ErrorStr> {
-      alt!(i, dont_work | dont_work | work2 | dont_work)
+    // the offsets are from the start of the input, not of the list
+    fn after_name(i: CompleteStr) -> IResult<CompleteStr, (CompleteStr, Vec<(usize, CompleteStr)>)> {
+      ws!(i, pair!(alpha, separated_list_spanned!(i, char!(','), alpha)))
     }
+    assert_eq!(
+      after_name(CompleteStr("x a, b")),
+      Ok((CompleteStr(""), (CompleteStr("x"), vec![(2, CompleteStr("a")), (5, CompleteStr("b"))])))
+    );
+  }
 
-    let a = &b"\tabcd"[..];
+  #[cfg(feature = "alloc")]
+  #[test]
+  fn many0_zero_width() {
+    named!(opt_a<&str, Vec<Option<&str> > >, ws!(many0!(opt!(tag!("a")))));
+    assert_eq!(opt_a("a  b"), Ok(("b", vec![Some("a"), None])));
+    assert_eq!(opt_a("b"), Ok(("b", vec![])));
+
+    named!(opt_a1<CompleteStr, Vec<Option<CompleteStr> > >, ws!(many1!(opt!(tag!("a")))));
+    assert_eq!(opt_a1(CompleteStr(" a a b")), Ok((CompleteStr("b"), vec![Some(CompleteStr("a")), Some(CompleteStr("a")), None])));
     assert_eq!(
-      alt1(a),
-      Err(Err::Error(nom_ws_error_position!(a, ErrorKind::Alt::<ErrorStr>)))
+      opt_a1(CompleteStr("b")),
+      Err(Err::Error(error_position!(CompleteStr("b"), ErrorKind::Many1)))
     );
-    assert_eq!(alt2(a), Ok((&b""[..], a)));
-    assert_eq!(alt3(a), Ok((a, &b""[..])));
+  }
 
-    named!(alt4<CompleteStr, CompleteStr>, ws!(alt!(tag!("abcd") | tag!("efgh"))));
+  #[cfg(feature = "alloc")]
+  #[test]
+  fn indented_block() {
+    use nom::{alpha, digit};
+    use super::inline_sp;
+
+    named!(top<CompleteStr, Vec<(CompleteStr, CompleteStr)> >, sep!(inline_sp, indented_block!(0, pair!(alpha, digit))));
+    named!(nested<CompleteStr, Vec<(CompleteStr, CompleteStr)> >, sep!(inline_sp, indented_block!(2, pair!(alpha, digit))));
+
+    let input = CompleteStr("  a 1\n    b 2\n    c 3\n  d 4\ne 5\n");
     assert_eq!(
-      alt4(CompleteStr("\tabcd")),
-      Ok((CompleteStr(""), CompleteStr(r"abcd")))
+      top(input),
+      Ok((CompleteStr("e 5\n"), vec![
+        (CompleteStr("a"), CompleteStr("1")),
+        (CompleteStr("b"), CompleteStr("2")),
+        (CompleteStr("c"), CompleteStr("3")),
+        (CompleteStr("d"), CompleteStr("4")),
+      ]))
     );
+    // the inner block stops when the line dedents to the parent level
     assert_eq!(
-      alt4(CompleteStr("  efgh ")),
-      Ok((CompleteStr(""), CompleteStr("efgh")))
+      nested(CompleteStr("    b 2\n    c 3\n  d 4\ne 5\n")),
+      Ok((CompleteStr("  d 4\ne 5\n"), vec![(CompleteStr("b"), CompleteStr("2")), (CompleteStr("c"), CompleteStr("3"))]))
+    );
+    assert_eq!(nested(CompleteStr("  d 4")), Ok((CompleteStr("  d 4"), vec![])));
+    // blank lines do not end the block, the next non blank line does
+    assert_eq!(
+      top(CompleteStr("  a 1\n\n \t\n  b 2\nc 3")),
+      Ok((CompleteStr("c 3"), vec![(CompleteStr("a"), CompleteStr("1")), (CompleteStr("b"), CompleteStr("2"))]))
+    );
+    assert_eq!(
+      nested(CompleteStr("    b 2\n\n  d 4")),
+      Ok((CompleteStr("\n  d 4"), vec![(CompleteStr("b"), CompleteStr("2"))]))
+    );
+    assert_eq!(top(CompleteStr("  a 1\n  \n")), Ok((CompleteStr("  \n"), vec![(CompleteStr("a"), CompleteStr("1"))])));
+    assert_eq!(
+      top(CompleteStr("  a 1 b\n")),
+      Err(Err::Error(error_position!(CompleteStr("b\n"), ErrorKind::CrLf)))
     );
-
-    // test the alternative syntax
-    named!(alt5<CompleteStr, bool>, ws!(alt!(tag!("abcd") => { |_| false } | tag!("efgh") => { |_| true })));
-    assert_eq!(alt5(CompleteStr("\tabcd")), Ok((CompleteStr(""), false)));
-    assert_eq!(alt5(CompleteStr("  efgh ")), Ok((CompleteStr(""), true)));
   }
 
-  /*FIXME: alt_complete works, but ws will return Incomplete on end of input
+  #[cfg(feature = "alloc")]
   #[test]
-  fn alt_complete() {
-    named!(ac<&[u8], &[u8]>,
-      ws!(alt_complete!(tag!("abcd") | tag!("ef") | tag!("ghi") | tag!("kl")))
-    );
+  fn sticky_newline_separator() {
+    use nom::alpha;
+    use super::{StickyNewlineSeparator, STICKY_NEWLINE_ERROR};
 
-    let a = &b""[..];
-    assert_eq!(ac(a), Err(Err::Error(nom_ws_error_position!(a, ErrorKind::Alt))));
-    let a = &b" \tef "[..];
-    assert_eq!(ac(a),Ok((&b""[..], &b"ef"[..])));
-    let a = &b" cde"[..];
-    assert_eq!(ac(a), Err(Err::Error(nom_ws_error_position!(&a[1..], ErrorKind::Alt))));
-  }
-  */
+    fn words<'a>(i: CompleteStr<'a>, separator: &StickyNewlineSeparator) -> IResult<CompleteStr<'a>, Vec<CompleteStr<'a>>> {
+      sep_with!(i, separator, many0!(alpha))
+    }
 
-  #[allow(unused_variables)]
-  #[test]
-  fn switch() {
-    named!(sw<CompleteStr,CompleteStr>,
-      ws!(switch!(take!(4),
-        CompleteStr("abcd") => take!(2) |
-        CompleteStr("efgh") => take!(4)
-      ))
+    let separator = StickyNewlineSeparator::new();
+    assert_eq!(
+      words(CompleteStr("a b\nc\n d"), &separator),
+      Ok((CompleteStr(""), vec![CompleteStr("a"), CompleteStr("b"), CompleteStr("c"), CompleteStr("d")]))
     );
+    assert!(separator.is_multiline());
 
-    let a = CompleteStr(" abcd ef gh");
-    assert_eq!(sw(a), Ok((CompleteStr("gh"), CompleteStr("ef"))));
+    // after the newline, the space between c and d is rejected
+    let separator = StickyNewlineSeparator::new();
+    assert_eq!(
+      words(CompleteStr("a b\nc d"), &separator),
+      Ok((CompleteStr(" d"), vec![CompleteStr("a"), CompleteStr("b"), CompleteStr("c")]))
+    );
 
-    let b = CompleteStr("\tefgh ijkl ");
-    assert_eq!(sw(b), Ok((CompleteStr(""), CompleteStr("ijkl"))));
-    let c = CompleteStr("afghijkl");
+    let separator = StickyNewlineSeparator::new();
+    named_args!(pair<'a>(separator: &'a StickyNewlineSeparator)<CompleteStr<'a>, (CompleteStr<'a>, CompleteStr<'a>, CompleteStr<'a>)>,
+      sep_with!(separator, tuple!(alpha, alpha, alpha))
+    );
     assert_eq!(
-      sw(c),
-      Err(Err::Error(nom_ws_error_position!(
-        CompleteStr("afghijkl"),
-        ErrorKind::Switch
-      )))
+      pair(CompleteStr("a\nb c"), &separator),
+      Err(Err::Error(error_position!(CompleteStr(" c"), ErrorKind::Custom(STICKY_NEWLINE_ERROR))))
     );
-  }
 
-  named!(str_parse(&str) -> &str, ws!(tag!("test")));
-  #[allow(unused_variables)]
-  #[test]
-  fn str_test() {
-    assert_eq!(str_parse(" \n   test\t a\nb"), Ok(("a\nb", "test")));
+    // functions are separators too
+    named!(plain<CompleteStr, Vec<CompleteStr> >, sep_with!(&sp, many0!(alpha)));
+    assert_eq!(plain(CompleteStr("a\nb c")), Ok((CompleteStr(""), vec![CompleteStr("a"), CompleteStr("b"), CompleteStr("c")])));
   }
 
-  // test whitespace parser generation for alt
-  named!(space, tag!(" "));
-  #[cfg(feature = "alloc")]
-  named!(pipeline_statement<&[u8], ()>,
-    ws!(
-      do_parse!(
-      tag!("pipeline") >>
-      attributes: delimited!(char!('{'),
-                             separated_list!(char!(','), alt!(
-                               space |
-                               space
-                             )),
-                             char!('}')) >>
-
-      ({
-        let _ = attributes;
-        ()
-      })
-    )
-  )
-  );
+  #[test]
+  fn ws_trailing() {
+    named!(pair<CompleteStr, (CompleteStr, CompleteStr)>, ws!(pair!(tag!("a"), tag!("b"))));
+    let rest = if cfg!(feature = "ws_no_trailing") { "  " } else { "" };
+    assert_eq!(pair(CompleteStr(" a b  ")), Ok((CompleteStr(rest), (CompleteStr("a"), CompleteStr("b")))));
 
-  #[cfg(feature = "alloc")]
-  named!(
-    fail<&[u8]>,
-    map!(many_till!(take!(1), ws!(tag!("."))), |(r, _)| r[0])
-  );
+    // `ws_with!` consumes it regardless of the feature
+    named!(with<CompleteStr, (CompleteStr, CompleteStr)>, ws_with!(::sp, pair!(tag!("a"), tag!("b"))));
+    assert_eq!(with(CompleteStr(" a b  ")), Ok((CompleteStr(""), (CompleteStr("a"), CompleteStr("b")))));
+  }
 }